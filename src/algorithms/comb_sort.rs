@@ -0,0 +1,109 @@
+use std::cmp::Ordering;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// Sorting against small sets without any additional memory, which improves on bubble sort.
+///
+/// Comb sort (Dobosiewicz, 1980) compares and swaps the elements that are `gap` positions apart, like bubble sort
+/// does with adjacent elements, and shrinks the gap by a factor of 1.3 after every pass. The large gaps move the
+/// small elements near the end of the data, which bubble sort moves by one position per pass, to the front early.
+/// Once the gap is 1, the passes continue until one makes no swaps. Gaps of 9 and 10 are replaced by 11, which
+/// avoids slow gap sequences (combsort11).
+///
+/// Comb sort is not stable.
+pub fn comb_sort<A: Ord>(data: &mut [A]) {
+    comb_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with comb sort, using the comparator function to order the elements.
+pub fn comb_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    comb_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with comb sort, ordering the elements by the keys the function extracts from them.
+pub fn comb_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    comb_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with comb sort, reporting the comparisons and swaps to the observer.
+pub fn comb_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    comb_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with comb sort, using the comparator function to order the elements, and reporting the swaps to
+/// the observer.
+pub fn comb_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
+    let mut gap = data.len();
+    let mut sorted = false;
+
+    while !sorted {
+        gap = match gap * 10 / 13 {
+            0 => 1,
+            9 | 10 => 11,
+            gap => gap,
+        };
+        sorted = gap == 1;
+
+        for i in gap .. data.len() {
+            if compare(&data[i - gap], &data[i]) == Ordering::Greater {
+                data.swap(i - gap, i);
+                observer.swapped(data, i - gap, i);
+                sorted = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::random_data;
+    use algorithms::sort_stats::SortStats;
+
+    #[test]
+    fn test_comb_sort() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        comb_sort(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
+
+        for &size in [0, 1, 2, 11, 12, 100, 1_000].iter() {
+            for &max in [3, u32::MAX].iter() {
+                let mut data = random_data(size, max);
+                let mut expected = data.clone();
+                expected.sort();
+
+                comb_sort(data.as_mut_slice());
+                assert_eq!(expected, data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_comb_sort_by() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        comb_sort_by(data.as_mut_slice(), |a, b| b.cmp(a));
+        assert_eq!([16, 9, 8, 5, 4, 3, 2, 1, 0], data.as_slice());
+
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        comb_sort_by_key(data.as_mut_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("a", 3), ("d", 4)], data.as_slice());
+    }
+
+    /// Sorted data takes a pass for every gap and no swaps.
+    #[test]
+    fn test_comb_sort_observed() {
+        let mut data: Vec<u32> = (0 .. 1_000).collect();
+        let stats = SortStats::new();
+        comb_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!(0, stats.swaps());
+        assert!(stats.comparisons() < 20 * 1_000, "{} comparisons", stats.comparisons());
+
+        let mut data = random_data(1_000, u32::MAX);
+        let stats = SortStats::new();
+        comb_sort_observed(data.as_mut_slice(), &stats);
+        assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(stats.comparisons() < 50 * 1_000, "{} comparisons", stats.comparisons());
+    }
+}
@@ -0,0 +1,300 @@
+//! Consistent hashing maps keys to nodes such that only a small fraction of the keys move when nodes are added or
+//! removed. With a plain `hash(key) % nodes`, nearly every key moves when the number of nodes changes.
+//!
+//! Scheme | Lookup | Notes
+//! :----- | :----: | :----
+//! HashRing | O(log(n * v)) | Any node can be removed. Needs `v` virtual nodes per node to balance the load.
+//! Rendezvous hashing | O(n) | Any node can be removed. Balanced without virtual nodes, and no memory use.
+//! Jump consistent hash | O(log n) | Nodes are numbered and can only be added or removed at the end.
+//!
+//! When going from `n` to `n + 1` nodes, all schemes move about `1 / (n + 1)` of the keys, all to the new node.
+use std::cmp::Ordering;
+use algorithms::murmur::murmur3_32_seed;
+
+/// A consistent hash ring (Karger et al., 1997).
+///
+/// Every node is placed at `virtual_nodes` points on a ring of 32-bit hashes, and a key belongs to the first node
+/// found clockwise from the hash of the key. The virtual nodes spread the load of every node over the ring; the
+/// load of a node deviates from the mean by about `1 / sqrt(virtual_nodes)`.
+pub struct HashRing<N> {
+    points: Vec<(u32, N)>,
+    nodes: Vec<N>,
+    virtual_nodes: u32,
+}
+
+impl<N: AsRef<[u8]> + Clone + PartialEq> HashRing<N> {
+    /// Constructs a new empty hash ring that places every node at `virtual_nodes` points.
+    pub fn new(virtual_nodes: u32) -> HashRing<N> {
+        if virtual_nodes == 0 {
+            panic!("the number of virtual nodes has to be at least 1");
+        }
+
+        HashRing {
+            points: Vec::new(),
+            nodes: Vec::new(),
+            virtual_nodes: virtual_nodes,
+        }
+    }
+
+    /// Adds the node to the ring. Returns false if the ring already contained it.
+    pub fn add_node(&mut self, node: N) -> bool {
+        if self.nodes.contains(&node) {
+            return false;
+        }
+
+        for replica in 0 .. self.virtual_nodes {
+            self.points.push((murmur3_32_seed(node.as_ref(), replica), node.clone()));
+        }
+
+        // Points of different nodes may collide, in which case the order of the nodes decides the owner.
+        self.points.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.as_ref().cmp(b.1.as_ref())));
+        self.nodes.push(node);
+        true
+    }
+
+    /// Removes the node from the ring. Returns false if the ring did not contain it.
+    pub fn remove_node(&mut self, node: &N) -> bool {
+        match self.nodes.iter().position(|n| n == node) {
+            Some(index) => {
+                self.nodes.remove(index);
+                self.points.retain(|point| point.1 != *node);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns the index of the first point clockwise from the hash of the key.
+    fn first_point(&self, key: &[u8]) -> usize {
+        let hash = murmur3_32_seed(key, 0);
+        let index = match self.points.binary_search_by(|point| {
+            if point.0 < hash { Ordering::Less } else { Ordering::Greater }
+        }) {
+            Ok(index) | Err(index) => index,
+        };
+
+        // Past the last point, the ring wraps around to the first one.
+        if index == self.points.len() { 0 } else { index }
+    }
+
+    /// Returns the node the key belongs to, or `None` if the ring is empty.
+    pub fn get(&self, key: &[u8]) -> Option<&N> {
+        if self.points.is_empty() {
+            return None;
+        }
+
+        Some(&self.points[self.first_point(key)].1)
+    }
+
+    /// Returns up to `replicas` distinct nodes for the key, in the order they are found clockwise from it.
+    ///
+    /// The first node is the one returned by `get()`. This is typically used to place the replicas of a key.
+    pub fn get_n(&self, key: &[u8], replicas: usize) -> Vec<&N> {
+        let mut nodes: Vec<&N> = Vec::with_capacity(replicas);
+        if self.points.is_empty() {
+            return nodes;
+        }
+
+        let first = self.first_point(key);
+        for i in 0 .. self.points.len() {
+            if nodes.len() == replicas {
+                break;
+            }
+
+            let node = &self.points[(first + i) % self.points.len()].1;
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+
+        nodes
+    }
+
+    /// Returns the nodes of the ring, in the order they were added.
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the ring has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/// Returns the weight of the node for the key. The hash of the node seeds the hash of the key.
+fn rendezvous_weight(key: &[u8], node: &[u8]) -> u32 {
+    murmur3_32_seed(key, murmur3_32_seed(node, 0))
+}
+
+/// Returns the node the key belongs to with rendezvous, or highest random weight, hashing (Thaler & Ravishankar,
+/// 1996), or `None` if there are no nodes.
+///
+/// Every node is weighted with a hash of the key and the node, and the key belongs to the node of the highest
+/// weight. When a node is removed, only its keys move, each to the node of its second highest weight.
+pub fn rendezvous<'a, N: AsRef<[u8]>>(key: &[u8], nodes: &'a [N]) -> Option<&'a N> {
+    nodes.iter().max_by(|a, b| {
+        rendezvous_weight(key, a.as_ref()).cmp(&rendezvous_weight(key, b.as_ref()))
+            .then_with(|| a.as_ref().cmp(b.as_ref()))
+    })
+}
+
+/// Returns up to `replicas` nodes for the key with rendezvous hashing, from the highest weight to the lowest.
+pub fn rendezvous_n<'a, N: AsRef<[u8]>>(key: &[u8], nodes: &'a [N], replicas: usize) -> Vec<&'a N> {
+    let mut weighted: Vec<(u32, &N)> = nodes.iter().map(|node| (rendezvous_weight(key, node.as_ref()), node)).collect();
+    weighted.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.as_ref().cmp(a.1.as_ref())));
+
+    weighted.into_iter().take(replicas).map(|(_, node)| node).collect()
+}
+
+/// Returns the bucket within `[0, buckets)` of the 64-bit key with Jump Consistent Hash (Lamping & Veach, 2014).
+///
+/// The key jumps forward through the buckets, driven by a linear congruential generator seeded with the key. When
+/// going from `n` to `n + 1` buckets, a key moves to the new bucket with a probability of `1 / (n + 1)` and stays
+/// otherwise. It needs no memory, but the buckets can only be numbered, not named.
+pub fn jump_consistent_hash(mut key: u64, buckets: u32) -> u32 {
+    if buckets == 0 {
+        panic!("the number of buckets has to be at least 1");
+    }
+
+    let mut bucket = -1i64;
+    let mut jump = 0i64;
+
+    while jump < buckets as i64 {
+        bucket = jump;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        jump = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    bucket as u32
+}
+
+/// Returns the bucket within `[0, buckets)` of the key with Jump Consistent Hash, using two murmur hashes of the key
+/// as the 64-bit key.
+pub fn jump_hash(key: &[u8], buckets: u32) -> u32 {
+    let hash = (murmur3_32_seed(key, 0) as u64) << 32 | murmur3_32_seed(key, 1) as u64;
+    jump_consistent_hash(hash, buckets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::key;
+
+    fn node_names(count: usize) -> Vec<String> {
+        (0 .. count).map(|i| format!("cache-{}", i)).collect()
+    }
+
+    /// Returns the fraction of the keys that map to a different node.
+    fn moved<A: PartialEq>(before: &[A], after: &[A]) -> f64 {
+        let moved = before.iter().zip(after.iter()).filter(|&(a, b)| a != b).count();
+        moved as f64 / before.len() as f64
+    }
+
+    #[test]
+    fn hash_ring() {
+        let mut ring = HashRing::new(100);
+        assert!(ring.is_empty());
+        assert_eq!(None, ring.get(b"key"));
+
+        for node in node_names(10) {
+            assert!(ring.add_node(node));
+        }
+        assert_eq!(false, ring.add_node("cache-0".to_string()));
+        assert_eq!(10, ring.len());
+
+        let replicas = ring.get_n(b"key", 3);
+        assert_eq!(3, replicas.len());
+        assert_eq!(ring.get(b"key"), Some(replicas[0]));
+        assert!(replicas[0] != replicas[1] && replicas[1] != replicas[2] && replicas[0] != replicas[2]);
+        assert_eq!(10, ring.get_n(b"key", 20).len());
+
+        assert!(ring.remove_node(&"cache-3".to_string()));
+        assert_eq!(false, ring.remove_node(&"cache-3".to_string()));
+        assert_eq!(9, ring.len());
+        assert!((0 .. 1000).all(|i| ring.get(&key(i)).unwrap() != "cache-3"));
+    }
+
+    #[test]
+    fn hash_ring_balance() {
+        let mut ring = HashRing::new(200);
+        for node in node_names(10) {
+            ring.add_node(node);
+        }
+
+        let mut loads = vec![0u32; 10];
+        for i in 0 .. 100_000 {
+            let node = ring.get(&key(i)).unwrap();
+            loads[ring.nodes().iter().position(|n| n == node).unwrap()] += 1;
+        }
+
+        let max = *loads.iter().max().unwrap();
+        let min = *loads.iter().min().unwrap();
+        assert!(max < 13_000 && min > 7_000, "{:?}", loads);
+    }
+
+    #[test]
+    fn hash_ring_moves_few_keys() {
+        let mut ring = HashRing::new(100);
+        for node in node_names(10) {
+            ring.add_node(node);
+        }
+
+        let before: Vec<String> = (0 .. 100_000).map(|i| ring.get(&key(i)).unwrap().clone()).collect();
+        ring.add_node("cache-10".to_string());
+        let after: Vec<String> = (0 .. 100_000).map(|i| ring.get(&key(i)).unwrap().clone()).collect();
+
+        // About 1/11 of the keys move, all to the new node.
+        let fraction = moved(&before, &after);
+        assert!(fraction > 0.06 && fraction < 0.12, "{} moved", fraction);
+        assert!(before.iter().zip(after.iter()).all(|(a, b)| a == b || b == "cache-10"));
+
+        // Removing the node again moves exactly those keys back.
+        ring.remove_node(&"cache-10".to_string());
+        let removed: Vec<String> = (0 .. 100_000).map(|i| ring.get(&key(i)).unwrap().clone()).collect();
+        assert_eq!(before, removed);
+    }
+
+    #[test]
+    fn rendezvous_moves_few_keys() {
+        let mut nodes = node_names(10);
+        assert_eq!(None, rendezvous(b"key", &[] as &[String]));
+
+        let replicas = rendezvous_n(b"key", &nodes, 3);
+        assert_eq!(3, replicas.len());
+        assert_eq!(rendezvous(b"key", &nodes), Some(replicas[0]));
+
+        let before: Vec<String> = (0 .. 100_000).map(|i| rendezvous(&key(i), &nodes).unwrap().clone()).collect();
+        nodes.push("cache-10".to_string());
+        let after: Vec<String> = (0 .. 100_000).map(|i| rendezvous(&key(i), &nodes).unwrap().clone()).collect();
+
+        let fraction = moved(&before, &after);
+        assert!(fraction > 0.08 && fraction < 0.1, "{} moved", fraction);
+        assert!(before.iter().zip(after.iter()).all(|(a, b)| a == b || b == "cache-10"));
+
+        // Removing another node moves only its own keys.
+        nodes.remove(4);
+        let removed: Vec<String> = (0 .. 100_000).map(|i| rendezvous(&key(i), &nodes).unwrap().clone()).collect();
+        assert!(after.iter().zip(removed.iter()).all(|(a, b)| a == b || a == "cache-4"));
+    }
+
+    #[test]
+    fn jump_consistent_hash_moves_few_keys() {
+        assert_eq!(0, jump_consistent_hash(12345, 1));
+        assert!((0 .. 1000).all(|i| jump_hash(&key(i), 7) < 7));
+
+        for buckets in 1 .. 20 {
+            let before: Vec<u32> = (0 .. 100_000).map(|i| jump_hash(&key(i), buckets)).collect();
+            let after: Vec<u32> = (0 .. 100_000).map(|i| jump_hash(&key(i), buckets + 1)).collect();
+
+            let expected = 1.0 / (buckets + 1) as f64;
+            let fraction = moved(&before, &after);
+            assert!((fraction - expected).abs() < 0.01, "{} moved from {} buckets", fraction, buckets);
+            assert!(before.iter().zip(after.iter()).all(|(&a, &b)| a == b || b == buckets));
+        }
+    }
+}
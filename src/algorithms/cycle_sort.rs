@@ -0,0 +1,208 @@
+use std::cmp::Ordering;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::slice;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// Sorting with the minimum number of writes, for memory where writes are expensive, e.g. flash memory.
+///
+/// Cycle sort (Haddon, 1990) takes every element in turn out of the data, and counts the elements that are less
+/// than it to find its sorted position. It writes the element there, takes out the element that was there, and
+/// continues with it until the cycle of the permutation returns to where it started, where the last element fills
+/// the gap. Every element is written once to its sorted position, and elements already in place are never written.
+/// Equal elements are placed after the ones that are already in place.
+///
+/// It performs `O(n^2)` comparisons in all cases, and is not stable.
+pub fn cycle_sort<A: Ord>(data: &mut [A]) {
+    cycle_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with cycle sort, using the comparator function to order the elements.
+pub fn cycle_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    cycle_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with cycle sort, ordering the elements by the keys the function extracts from them.
+pub fn cycle_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    cycle_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with cycle sort, reporting the comparisons and writes to the observer.
+pub fn cycle_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    cycle_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with cycle sort, using the comparator function to order the elements, and reporting the writes to
+/// the observer.
+///
+/// Every element that is placed is reported as a write of its position.
+pub fn cycle_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
+    let length = data.len();
+    let data = data.as_mut_ptr();
+
+    for start in 0 .. length {
+        unsafe {
+            let mut position = sorted_position(data, start, length, &*data.add(start), &mut compare);
+            if position == start {
+                continue;
+            }
+
+            // The element at the start is held out of the data until the element that belongs there is found. If the
+            // comparator panics, the hole writes the held element back to the start.
+            let mut hole = Hole {
+                element: ManuallyDrop::new(ptr::read(data.add(start))),
+                destination: data.add(start),
+            };
+
+            loop {
+                // Equal elements that are already in place are skipped.
+                while compare(&*data.add(position), &hole.element) == Ordering::Equal {
+                    position += 1;
+                }
+
+                ptr::swap(data.add(position), &mut *hole.element);
+                observer.wrote(slice::from_raw_parts(data, length), position .. position + 1);
+
+                position = sorted_position(data, start, length, &hole.element, &mut compare);
+                if position == start {
+                    break;
+                }
+            }
+
+            drop(hole);
+            observer.wrote(slice::from_raw_parts(data, length), start .. start + 1);
+        }
+    }
+}
+
+/// Returns the sorted position of the element in the data from `start`, which is after all the other elements that
+/// are less than it.
+unsafe fn sorted_position<A, F: FnMut(&A, &A) -> Ordering>(
+    data: *const A,
+    start: usize,
+    length: usize,
+    element: &A,
+    compare: &mut F
+) -> usize {
+    let mut position = start;
+    for i in start + 1 .. length {
+        if compare(&*data.add(i), element) == Ordering::Less {
+            position += 1;
+        }
+    }
+    position
+}
+
+/// An element that was taken out of the data, which is written to the destination when the hole is dropped.
+struct Hole<A> {
+    element: ManuallyDrop<A>,
+    destination: *mut A,
+}
+
+impl<A> Drop for Hole<A> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::copy_nonoverlapping(&*self.element, self.destination, 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+    use std::rc::Rc;
+    use rand::Rng;
+    use test_helpers::{random_data, rng};
+    use algorithms::sort_stats::{SortStats, SortTrace};
+
+    #[test]
+    fn test_cycle_sort() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        cycle_sort(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
+
+        for &size in [0, 1, 2, 100, 1_000].iter() {
+            for &max in [1, 3, u32::MAX].iter() {
+                let mut data = random_data(size, max);
+                let mut expected = data.clone();
+                expected.sort();
+
+                cycle_sort(data.as_mut_slice());
+                assert_eq!(expected, data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cycle_sort_by() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        cycle_sort_by(data.as_mut_slice(), |a, b| b.cmp(a));
+        assert_eq!([16, 9, 8, 5, 4, 3, 2, 1, 0], data.as_slice());
+
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        cycle_sort_by_key(data.as_mut_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("a", 3), ("d", 4)], data.as_slice());
+    }
+
+    /// A permutation of distinct elements takes exactly one write for every element that is not in place, which is
+    /// the minimum.
+    #[test]
+    fn test_cycle_sort_is_write_minimal() {
+        let mut rng = rng();
+        for &size in [1, 10, 100, 1_000].iter() {
+            let mut data: Vec<usize> = (0 .. size).collect();
+            rng.shuffle(data.as_mut_slice());
+            let misplaced = data.iter().enumerate().filter(|&(i, &element)| i != element).count();
+
+            let stats = SortStats::new();
+            cycle_sort_observed(data.as_mut_slice(), &stats);
+            assert_eq!((0 .. size).collect::<Vec<usize>>(), data);
+            assert_eq!(misplaced, stats.writes());
+            assert_eq!(0, stats.swaps());
+        }
+
+        // Every element of the trace is written to its final position. The start of the cycle keeps its old element
+        // until the last element of the cycle is written there.
+        let mut data = vec![2, 0, 1, 3];
+        let trace = SortTrace::new(&data);
+        cycle_sort_observed(data.as_mut_slice(), &trace);
+        assert_eq!(vec![vec![2, 0, 1, 3], vec![2, 0, 2, 3], vec![2, 1, 2, 3], vec![0, 1, 2, 3]], trace.states());
+
+        // Duplicates in place are not written.
+        let mut data = vec![1, 1, 0, 0, 1];
+        let stats = SortStats::new();
+        cycle_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!([0, 0, 1, 1, 1], data.as_slice());
+        assert_eq!(4, stats.writes());
+    }
+
+    /// A panicking comparator leaves every element in the data exactly once.
+    #[test]
+    fn test_cycle_sort_panic_safety() {
+        let value = Rc::new(());
+        let mut data: Vec<(u32, Rc<()>)> = (0 .. 100).map(|i| ((i * 37) % 100, value.clone())).collect();
+        let mut comparisons = 0;
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            cycle_sort_by(data.as_mut_slice(), |a, b| {
+                comparisons += 1;
+                if comparisons == 500 {
+                    panic!("comparator failure");
+                }
+                a.0.cmp(&b.0)
+            });
+        }));
+
+        assert!(result.is_err());
+        let mut keys: Vec<u32> = data.iter().map(|element| element.0).collect();
+        keys.sort();
+        assert_eq!((0 .. 100).collect::<Vec<u32>>(), keys);
+
+        drop(data);
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+}
@@ -0,0 +1,442 @@
+//! Sorting of data sets that do not fit in memory.
+//!
+//! The input is read in chunks that fit in the memory limit. Every chunk is sorted in memory and written to a temp
+//! file as a sorted run. The runs are then merged into the output with a k-way merge, which keeps the next record of
+//! every run in a heap. When there are more runs than the fan-in, groups of runs are first merged into longer runs.
+use std::cmp::{self, Ordering};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{self, AtomicUsize};
+use algorithms::tim_sort::tim_sort;
+use data_structures::binary_heap::BinaryHeap;
+
+/// The maximum number of runs that are merged at once, which bounds the number of open files.
+pub static MAX_FAN_IN: usize = 64usize;
+
+/// The minimum size of the buffer of every run while merging.
+static MIN_BUFFER_SIZE: usize = 4096usize;
+
+/// The number of bytes of a byte string that are allocated at once when decoding it, so that a corrupt length does
+/// not allocate more memory than the stream actually holds.
+static DECODE_CHUNK_SIZE: u64 = 65536u64;
+
+/// Numbers the temp files, so that concurrent sorts in the same process do not share them.
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// A record that can be written to and read back from a run.
+pub trait Record: Sized {
+    /// Writes the record to the writer.
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Reads the next record from the reader. Returns `None` at the end of the stream.
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Option<Self>>;
+
+    /// Returns the number of bytes of memory the record takes, which is counted against the memory limit.
+    fn size(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+}
+
+/// Fills the buffer from the reader. Returns false if the stream ended before the first byte, and an error if it
+/// ended after it.
+fn read_exact_or_end<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record")),
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+macro_rules! record_integer {
+    ($($t:ty),*) => {
+        $(
+            /// Integers are encoded as little-endian bytes.
+            impl Record for $t {
+                fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn decode<R: Read>(reader: &mut R) -> io::Result<Option<$t>> {
+                    let mut bytes = [0u8; mem::size_of::<$t>()];
+                    if read_exact_or_end(reader, &mut bytes)? {
+                        Ok(Some(<$t>::from_le_bytes(bytes)))
+                    } else {
+                        Ok(None)
+                    }
+                }
+            }
+        )*
+    }
+}
+
+record_integer!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Byte strings are encoded as their length as a little-endian `u64`, followed by the bytes.
+impl Record for Vec<u8> {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len() as u64).encode(writer)?;
+        writer.write_all(self)
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+        let length = match u64::decode(reader)? {
+            Some(length) => length,
+            None => return Ok(None),
+        };
+
+        // The vector grows with the bytes that are read, rather than trusting the length up front.
+        let mut bytes = Vec::with_capacity(cmp::min(length, DECODE_CHUNK_SIZE) as usize);
+        reader.take(length).read_to_end(&mut bytes)?;
+        if (bytes.len() as u64) < length {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record"));
+        }
+        Ok(Some(bytes))
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<Vec<u8>>() + self.len()
+    }
+}
+
+/// Strings are encoded like byte strings.
+impl Record for String {
+    fn encode<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len() as u64).encode(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn decode<R: Read>(reader: &mut R) -> io::Result<Option<String>> {
+        match Vec::<u8>::decode(reader)? {
+            Some(bytes) => String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            None => Ok(None),
+        }
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<String>() + self.len()
+    }
+}
+
+/// The temp files of the runs, which are removed when they are dropped, also when the sort fails.
+struct Runs {
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for path in self.paths.iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// The next record of a run in the merge heap.
+///
+/// The order is reversed, because `BinaryHeap` pops the greatest element first. Equal records are taken from the
+/// earlier run first, which keeps the sort stable.
+struct Head<A> {
+    record: A,
+    run: usize,
+}
+
+impl<A: Ord> Ord for Head<A> {
+    fn cmp(&self, other: &Head<A>) -> Ordering {
+        other.record.cmp(&self.record).then(other.run.cmp(&self.run))
+    }
+}
+
+impl<A: Ord> PartialOrd for Head<A> {
+    fn partial_cmp(&self, other: &Head<A>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Ord> PartialEq for Head<A> {
+    fn eq(&self, other: &Head<A>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<A: Ord> Eq for Head<A> {}
+
+/// An external merge sort, which sorts streams of records with a bounded amount of memory.
+///
+/// The sort is stable. It reads and writes every record `1 + ceil(log_f(r))` times for `r` runs and a fan-in `f`.
+///
+/// While merging, the buffers of the runs and of the merged output share the memory limit, which bounds the
+/// fan-in to `memory_limit / MIN_BUFFER_SIZE - 1`, and to at least two and at most `MAX_FAN_IN` runs. Limits below
+/// `3 * MIN_BUFFER_SIZE` are thus exceeded by the buffers of a two-way merge. The buffers of the input and output
+/// streams that are passed to `sort` are not counted.
+pub struct ExternalSorter {
+    memory_limit: usize,
+    temp_dir: PathBuf,
+}
+
+impl ExternalSorter {
+    /// Creates a new sorter that keeps at most about `memory_limit` bytes of records in memory, and writes the runs
+    /// to the temp directory of the system.
+    pub fn new(memory_limit: usize) -> ExternalSorter {
+        ExternalSorter::with_temp_dir(memory_limit, env::temp_dir())
+    }
+
+    /// Creates a new sorter that keeps at most about `memory_limit` bytes of records in memory, and writes the runs
+    /// to the given directory.
+    pub fn with_temp_dir(memory_limit: usize, temp_dir: PathBuf) -> ExternalSorter {
+        if memory_limit == 0 {
+            panic!("the memory limit has to be positive");
+        }
+
+        ExternalSorter {
+            memory_limit: memory_limit,
+            temp_dir: temp_dir,
+        }
+    }
+
+    /// Reads the records from the input, and writes them sorted to the output.
+    ///
+    /// Returns the number of runs that were written to temp files, which is zero if the input fit in memory.
+    pub fn sort<A: Record + Ord, R: Read, W: Write>(&self, input: R, output: W) -> io::Result<usize> {
+        let mut input = BufReader::new(input);
+        let mut output = BufWriter::new(output);
+        let mut runs = Runs { paths: Vec::new() };
+
+        let mut chunk = Vec::new();
+        let mut chunk_size = 0;
+        while let Some(record) = A::decode(&mut input)? {
+            chunk_size += record.size();
+            chunk.push(record);
+
+            if chunk_size >= self.memory_limit {
+                self.spill(&mut chunk, &mut runs)?;
+                chunk_size = 0;
+            }
+        }
+
+        if runs.paths.is_empty() {
+            tim_sort(chunk.as_mut_slice());
+            for record in chunk.iter() {
+                record.encode(&mut output)?;
+            }
+            output.flush()?;
+            return Ok(0);
+        }
+
+        if !chunk.is_empty() {
+            self.spill(&mut chunk, &mut runs)?;
+        }
+        let spilled = runs.paths.len();
+
+        // Merging consecutive groups keeps equal records in the order of their runs.
+        let fan_in = self.fan_in();
+        while runs.paths.len() > fan_in {
+            let mut merged = Runs { paths: Vec::new() };
+            for group in runs.paths.chunks(fan_in) {
+                let path = self.temp_path();
+                merged.paths.push(path.clone());
+                let mut writer = BufWriter::with_capacity(self.buffer_size(group.len()), File::create(&path)?);
+                self.merge::<A, _>(group, &mut writer)?;
+                writer.flush()?;
+            }
+            runs = merged;
+        }
+
+        self.merge::<A, _>(&runs.paths, &mut output)?;
+        output.flush()?;
+        Ok(spilled)
+    }
+
+    /// Sorts the chunk and writes it to a new run.
+    fn spill<A: Record + Ord>(&self, chunk: &mut Vec<A>, runs: &mut Runs) -> io::Result<()> {
+        tim_sort(chunk.as_mut_slice());
+
+        let path = self.temp_path();
+        runs.paths.push(path.clone());
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for record in chunk.drain(..) {
+            record.encode(&mut writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Returns the number of runs that are merged at once, whose buffers fit in the memory limit together with the
+    /// buffer of the output.
+    fn fan_in(&self) -> usize {
+        cmp::max(2, cmp::min(MAX_FAN_IN, (self.memory_limit / MIN_BUFFER_SIZE).saturating_sub(1)))
+    }
+
+    /// Returns the size of the buffers of a merge of the given number of runs and its output.
+    fn buffer_size(&self, runs: usize) -> usize {
+        cmp::max(self.memory_limit / (runs + 1), MIN_BUFFER_SIZE)
+    }
+
+    /// Merges the sorted runs into the output with a k-way merge.
+    fn merge<A: Record + Ord, W: Write>(&self, paths: &[PathBuf], output: &mut W) -> io::Result<()> {
+        let buffer_size = self.buffer_size(paths.len());
+        let mut readers = Vec::with_capacity(paths.len());
+        for path in paths.iter() {
+            readers.push(BufReader::with_capacity(buffer_size, File::open(path)?));
+        }
+
+        let mut heap = BinaryHeap::new();
+        for (run, reader) in readers.iter_mut().enumerate() {
+            if let Some(record) = A::decode(reader)? {
+                heap.push(Head { record: record, run: run });
+            }
+        }
+
+        while let Some(Head { record, run }) = heap.pop() {
+            record.encode(output)?;
+            if let Some(next) = A::decode(&mut readers[run])? {
+                heap.push(Head { record: next, run: run });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn temp_path(&self) -> PathBuf {
+        let run = NEXT_RUN.fetch_add(1, atomic::Ordering::Relaxed);
+        self.temp_dir.join(format!("external_sort_{}_{}.run", process::id(), run))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use test_helpers::rng;
+
+    fn encode<A: Record>(records: &[A]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for record in records.iter() {
+            record.encode(&mut bytes).unwrap();
+        }
+        bytes
+    }
+
+    fn decode<A: Record>(mut bytes: &[u8]) -> Vec<A> {
+        let mut records = Vec::new();
+        while let Some(record) = A::decode(&mut bytes).unwrap() {
+            records.push(record);
+        }
+        records
+    }
+
+    /// Creates an empty directory for the runs of a single test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("external_sort_test_{}_{}", process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_record_encoding() {
+        let numbers = vec![0u32, 1, u32::MAX, 123_456];
+        assert_eq!(16, encode(&numbers).len());
+        assert_eq!(numbers, decode::<u32>(&encode(&numbers)));
+
+        let numbers = vec![i64::MIN, -1, 0, i64::MAX];
+        assert_eq!(numbers, decode::<i64>(&encode(&numbers)));
+
+        let strings = vec!["".to_string(), "hello".to_string(), "wörld".to_string()];
+        assert_eq!(strings, decode::<String>(&encode(&strings)));
+
+        // A record cut in half is an error rather than the end of the stream.
+        let bytes = encode(&[1u32, 2]);
+        assert_eq!(io::ErrorKind::UnexpectedEof, u32::decode(&mut &bytes[..6][4..]).unwrap_err().kind());
+        let bytes = encode(&["hello".to_string()]);
+        assert_eq!(io::ErrorKind::UnexpectedEof, String::decode(&mut &bytes[..10]).unwrap_err().kind());
+
+        // A corrupt length is not allocated up front.
+        let mut bytes = encode(&[u64::MAX]);
+        bytes.extend_from_slice(b"abc");
+        assert_eq!(io::ErrorKind::UnexpectedEof, Vec::<u8>::decode(&mut &bytes[..]).unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_fan_in() {
+        assert_eq!(2, ExternalSorter::new(1024).fan_in());
+        assert_eq!(2, ExternalSorter::new(3 * 4096).fan_in());
+        assert_eq!(15, ExternalSorter::new(64 * 1024).fan_in());
+        assert_eq!(MAX_FAN_IN, ExternalSorter::new(1 << 20).fan_in());
+        assert_eq!(4096, ExternalSorter::new(64 * 1024).buffer_size(15));
+    }
+
+    #[test]
+    fn test_external_sort() {
+        let dir = temp_dir("numbers");
+        let mut rng = rng();
+        let numbers: Vec<u64> = (0 .. 20_000).map(|_| rng.gen()).collect();
+
+        // 1 KiB fits 128 numbers, so the input is split into 157 runs, which are merged two at a time.
+        let mut output = Vec::new();
+        let runs = ExternalSorter::with_temp_dir(1024, dir.clone()).sort::<u64, _, _>(&encode(&numbers)[..], &mut output).unwrap();
+        assert_eq!(157, runs);
+
+        let mut expected = numbers.clone();
+        expected.sort();
+        assert_eq!(expected, decode::<u64>(&output));
+
+        // The runs are removed.
+        assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_external_sort_strings() {
+        let dir = temp_dir("strings");
+        let mut rng = rng();
+        let strings: Vec<String> = (0 .. 5_000).map(|_| format!("record-{}", rng.gen_range(0, 1_000))).collect();
+
+        let mut output = Vec::new();
+        let runs = ExternalSorter::with_temp_dir(4096, dir.clone()).sort::<String, _, _>(&encode(&strings)[..], &mut output).unwrap();
+        assert!(runs > 1);
+
+        let mut expected = strings.clone();
+        expected.sort();
+        assert_eq!(expected, decode::<String>(&output));
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_external_sort_in_memory() {
+        let dir = temp_dir("in_memory");
+        let sorter = ExternalSorter::with_temp_dir(1 << 20, dir.clone());
+
+        let mut output = Vec::new();
+        assert_eq!(0, sorter.sort::<u32, _, _>(&encode(&[3u32, 1, 2])[..], &mut output).unwrap());
+        assert_eq!(vec![1u32, 2, 3], decode::<u32>(&output));
+
+        let mut output = Vec::new();
+        assert_eq!(0, sorter.sort::<u32, _, _>(io::empty(), &mut output).unwrap());
+        assert!(output.is_empty());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    /// A truncated input fails the sort, and the runs written so far are removed.
+    #[test]
+    fn test_external_sort_truncated_input() {
+        let dir = temp_dir("truncated");
+        let numbers: Vec<u32> = (0 .. 1_000).rev().collect();
+        let bytes = encode(&numbers);
+
+        let mut output = Vec::new();
+        let result = ExternalSorter::with_temp_dir(256, dir.clone()).sort::<u32, _, _>(&bytes[..bytes.len() - 1], &mut output);
+        assert_eq!(io::ErrorKind::UnexpectedEof, result.unwrap_err().kind());
+        assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+//! CityHash is a family of non-cryptographic hash functions by Google, which is fast for short and long keys alike.
+//!
+//! This implementation is based on CityHash64 of version 1.1. Keys of up to 64 bytes are hashed by specialized
+//! functions for their length, and longer keys are processed in 64-byte chunks with a 56-byte state.
+static K0: u64 = 0xc3a5c85c97cb3127u64;
+static K1: u64 = 0xb492b66fbe98f273u64;
+static K2: u64 = 0x9ae16a3b2f90404fu64;
+static K_MUL: u64 = 0x9ddfea08eb382d69u64;
+
+fn get_u32(data: &[u8]) -> u64 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as u64
+}
+
+fn get_u64(data: &[u8]) -> u64 {
+    (get_u32(&data[4..]) << 32) | get_u32(data)
+}
+
+#[inline]
+fn shift_mix(value: u64) -> u64 {
+    value ^ (value >> 47)
+}
+
+/// Hashes two 64-bit values into one, which is the `Hash128to64` of CityHash with a variable multiplier.
+#[inline]
+fn hash_16(u: u64, v: u64, mul: u64) -> u64 {
+    let a = shift_mix((u ^ v).wrapping_mul(mul));
+    let b = shift_mix((v ^ a).wrapping_mul(mul));
+    b.wrapping_mul(mul)
+}
+
+fn hash_0_to_16(data: &[u8]) -> u64 {
+    let length = data.len() as u64;
+
+    if data.len() >= 8 {
+        let mul = K2.wrapping_add(length * 2);
+        let a = get_u64(data).wrapping_add(K2);
+        let b = get_u64(&data[data.len() - 8..]);
+        let c = b.rotate_right(37).wrapping_mul(mul).wrapping_add(a);
+        let d = a.rotate_right(25).wrapping_add(b).wrapping_mul(mul);
+        hash_16(c, d, mul)
+    } else if data.len() >= 4 {
+        let mul = K2.wrapping_add(length * 2);
+        let a = get_u32(data);
+        hash_16(length + (a << 3), get_u32(&data[data.len() - 4..]), mul)
+    } else if !data.is_empty() {
+        let a = data[0] as u32;
+        let b = data[data.len() >> 1] as u32;
+        let c = data[data.len() - 1] as u32;
+        let y = a + (b << 8);
+        let z = length as u32 + (c << 2);
+        shift_mix((y as u64).wrapping_mul(K2) ^ (z as u64).wrapping_mul(K0)).wrapping_mul(K2)
+    } else {
+        K2
+    }
+}
+
+fn hash_17_to_32(data: &[u8]) -> u64 {
+    let length = data.len();
+    let mul = K2.wrapping_add(length as u64 * 2);
+    let a = get_u64(data).wrapping_mul(K1);
+    let b = get_u64(&data[8..]);
+    let c = get_u64(&data[length - 8..]).wrapping_mul(mul);
+    let d = get_u64(&data[length - 16..]).wrapping_mul(K2);
+
+    hash_16(
+        a.wrapping_add(b).rotate_right(43).wrapping_add(c.rotate_right(30)).wrapping_add(d),
+        a.wrapping_add(b.wrapping_add(K2).rotate_right(18)).wrapping_add(c),
+        mul
+    )
+}
+
+fn hash_33_to_64(data: &[u8]) -> u64 {
+    let length = data.len();
+    let mul = K2.wrapping_add(length as u64 * 2);
+    let a = get_u64(data).wrapping_mul(K2);
+    let b = get_u64(&data[8..]);
+    let c = get_u64(&data[length - 24..]);
+    let d = get_u64(&data[length - 32..]);
+    let e = get_u64(&data[16..]).wrapping_mul(K2);
+    let f = get_u64(&data[24..]).wrapping_mul(9);
+    let g = get_u64(&data[length - 8..]);
+    let h = get_u64(&data[length - 16..]).wrapping_mul(mul);
+
+    let u = a.wrapping_add(g).rotate_right(43).wrapping_add(b.rotate_right(30).wrapping_add(c).wrapping_mul(9));
+    let v = (a.wrapping_add(g) ^ d).wrapping_add(f).wrapping_add(1);
+    let w = u.wrapping_add(v).wrapping_mul(mul).swap_bytes().wrapping_add(h);
+    let x = e.wrapping_add(f).rotate_right(42).wrapping_add(c);
+    let y = v.wrapping_add(w).wrapping_mul(mul).swap_bytes().wrapping_add(g).wrapping_mul(mul);
+    let z = e.wrapping_add(f).wrapping_add(c);
+    let a = x.wrapping_add(z).wrapping_mul(mul).wrapping_add(y).swap_bytes().wrapping_add(b);
+    let b = shift_mix(z.wrapping_add(a).wrapping_mul(mul).wrapping_add(d).wrapping_add(h)).wrapping_mul(mul);
+    b.wrapping_add(x)
+}
+
+/// Returns two 64-bit hashes of the 32 bytes `w`, `x`, `y` and `z`, mixed with the two seeds `a` and `b`.
+#[inline]
+fn weak_hash_32(data: &[u8], a: u64, b: u64) -> (u64, u64) {
+    let (w, x, y, z) = (get_u64(data), get_u64(&data[8..]), get_u64(&data[16..]), get_u64(&data[24..]));
+
+    let a = a.wrapping_add(w);
+    let b = b.wrapping_add(a).wrapping_add(z).rotate_right(21);
+    let c = a;
+    let a = a.wrapping_add(x).wrapping_add(y);
+    let b = b.wrapping_add(a.rotate_right(44));
+    (a.wrapping_add(z), b.wrapping_add(c))
+}
+
+pub fn city_hash64(data: &[u8]) -> u64 {
+    let length = data.len();
+
+    if length <= 16 {
+        return hash_0_to_16(data);
+    } else if length <= 32 {
+        return hash_17_to_32(data);
+    } else if length <= 64 {
+        return hash_33_to_64(data);
+    }
+
+    // The state is seeded from the last 64 bytes, so the chunks below may overlap them.
+    let mut x = get_u64(&data[length - 40..]);
+    let mut y = get_u64(&data[length - 16..]).wrapping_add(get_u64(&data[length - 56..]));
+    let mut z = hash_16(get_u64(&data[length - 48..]).wrapping_add(length as u64), get_u64(&data[length - 24..]), K_MUL);
+    let mut v = weak_hash_32(&data[length - 64..], length as u64, z);
+    let mut w = weak_hash_32(&data[length - 32..], y.wrapping_add(K1), x);
+    x = x.wrapping_mul(K1).wrapping_add(get_u64(data));
+
+    // The chunks cover the data up to the last 1 to 64 bytes.
+    for chunk in data[.. (length - 1) & !63].chunks(64) {
+        x = x.wrapping_add(y).wrapping_add(v.0).wrapping_add(get_u64(&chunk[8..])).rotate_right(37).wrapping_mul(K1);
+        y = y.wrapping_add(v.1).wrapping_add(get_u64(&chunk[48..])).rotate_right(42).wrapping_mul(K1);
+        x ^= w.1;
+        y = y.wrapping_add(v.0).wrapping_add(get_u64(&chunk[40..]));
+        z = z.wrapping_add(w.0).rotate_right(33).wrapping_mul(K1);
+        v = weak_hash_32(chunk, v.1.wrapping_mul(K1), x.wrapping_add(w.0));
+        w = weak_hash_32(&chunk[32..], z.wrapping_add(w.1), y.wrapping_add(get_u64(&chunk[16..])));
+        ::std::mem::swap(&mut z, &mut x);
+    }
+
+    hash_16(
+        hash_16(v.0, w.0, K_MUL).wrapping_add(shift_mix(y).wrapping_mul(K1)).wrapping_add(z),
+        hash_16(v.1, w.1, K_MUL).wrapping_add(x),
+        K_MUL
+    )
+}
+
+/// CityHash64 with a seed, which is mixed into the unseeded hash.
+pub fn city_hash64_seed(data: &[u8], seed: u64) -> u64 {
+    hash_16(city_hash64(data).wrapping_sub(K2), seed, K_MUL)
+}
+
+/// The expected values are the output of the CityHash v1.1 reference code for `CityHash64` and
+/// `CityHash64WithSeed`, which covers each of the length classes and inputs of several chunks.
+#[test]
+fn basic_tests() {
+    // The empty key hashes to the constant `k2`.
+    assert_eq!(0x9ae16a3b2f90404f, city_hash64("".as_bytes()));
+
+    assert_eq!(0xb3454265b6df75e3, city_hash64("a".as_bytes()));
+    assert_eq!(0xb48be5a931380ce8, city_hash64("hello".as_bytes()));
+    assert_eq!(0x588fb7478bd6b01b, city_hash64("hello world".as_bytes()));
+    assert_eq!(0x285762441555f5f9, city_hash64("I will not buy this record".as_bytes()));
+    assert_eq!(0xc268724928feca7d, city_hash64("The quick brown fox jumps over the lazy dog".as_bytes()));
+    assert_eq!(0xe81ab32abd5d8138, city_hash64("0123456789012345678901234567890123456789012345678901234567890123456789".as_bytes()));
+
+    assert_eq!(0x17e038e48942f71b, city_hash64_seed("foobar".as_bytes(), 1));
+    assert_eq!(0x684bbd54df2b64f9, city_hash64_seed("foobar".as_bytes(), 2));
+}
+
+/// The prefixes of `[0, 7, 14, ...]` at the bounds of the length classes.
+#[test]
+fn length_classes() {
+    let data: Vec<u8> = (0 .. 300).map(|i| (i * 7) as u8).collect();
+    let expected = [
+        (1, 0xbe6056edf5e94b54),
+        (4, 0x05112b9e6277f665),
+        (8, 0xdc480bbce8c737a7),
+        (16, 0xfbb2740319962d9b),
+        (17, 0xa55b9970b7b70074),
+        (32, 0x0a9d86aa8d37da5c),
+        (33, 0xb0b792ea4aa52827),
+        (64, 0x00e74408f26f0006),
+        (65, 0x660af9e281226458),
+        (128, 0x3ce11b99a5b026e0),
+        (129, 0x8e081729ad83209c),
+        (300, 0x33aa12a86965f4fe),
+    ];
+
+    for &(length, hash) in expected.iter() {
+        assert_eq!(hash, city_hash64(&data[..length]), "length {}", length);
+    }
+}
+
+#[test]
+fn all_lengths() {
+    let data: Vec<u8> = (0 .. 256).map(|i| (i * 7) as u8).collect();
+    let mut hashes: Vec<u64> = (0 .. data.len()).map(|length| city_hash64(&data[..length])).collect();
+    hashes.sort();
+    hashes.dedup();
+    assert_eq!(data.len(), hashes.len());
+}
@@ -0,0 +1,91 @@
+//! CRC32 is a cyclic redundancy check, which is designed to detect accidental changes to data.
+//!
+//! This implementation computes the common IEEE 802.3 variant (as used by zlib, gzip and PNG) with the reflected
+//! polynomial `0xedb88320`. The table-driven version processes a byte per step, while the slice-by-8 version
+//! uses eight tables to process eight bytes per step.
+const POLYNOMIAL: u32 = 0xedb88320u32;
+
+const fn make_tables() -> [[u32; 256]; 8] {
+    let mut tables = [[0u32; 256]; 8];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ POLYNOMIAL } else { crc >> 1 };
+            bit += 1;
+        }
+
+        tables[0][i] = crc;
+        i += 1;
+    }
+
+    // Table `t` advances the CRC of a byte by `t` more zero bytes.
+    let mut i = 0;
+    while i < 256 {
+        let mut t = 1;
+        while t < 8 {
+            let previous = tables[t - 1][i];
+            tables[t][i] = (previous >> 8) ^ tables[0][(previous & 0xff) as usize];
+            t += 1;
+        }
+
+        i += 1;
+    }
+
+    tables
+}
+
+static TABLES: [[u32; 256]; 8] = make_tables();
+
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_slice_by_8(0, data)
+}
+
+/// Updates the CRC of the preceding data with the given data, one byte at a time.
+///
+/// Pass 0 as the CRC to start a new checksum.
+pub fn crc32_table(crc: u32, data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!crc, |crc, &byte| (crc >> 8) ^ TABLES[0][((crc ^ byte as u32) & 0xff) as usize]);
+    !crc
+}
+
+/// Updates the CRC of the preceding data with the given data, eight bytes at a time.
+///
+/// Pass 0 as the CRC to start a new checksum. The result is identical to `crc32_table`.
+pub fn crc32_slice_by_8(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    let mut chunks = data.chunks_exact(8);
+
+    for chunk in chunks.by_ref() {
+        let one = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) ^ crc;
+        let two = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+
+        crc = TABLES[7][(one & 0xff) as usize] ^
+              TABLES[6][((one >> 8) & 0xff) as usize] ^
+              TABLES[5][((one >> 16) & 0xff) as usize] ^
+              TABLES[4][(one >> 24) as usize] ^
+              TABLES[3][(two & 0xff) as usize] ^
+              TABLES[2][((two >> 8) & 0xff) as usize] ^
+              TABLES[1][((two >> 16) & 0xff) as usize] ^
+              TABLES[0][(two >> 24) as usize];
+    }
+
+    crc32_table(!crc, chunks.remainder())
+}
+
+#[test]
+fn basic_tests() {
+    let fox = "The quick brown fox jumps over the lazy dog".as_bytes();
+
+    assert_eq!(POLYNOMIAL, TABLES[0][128]);
+    assert_eq!(0, crc32("".as_bytes()));
+    assert_eq!(0xcbf43926, crc32("123456789".as_bytes()));
+    assert_eq!(0x414fa339, crc32(fox));
+
+    for split in 0 .. fox.len() {
+        assert_eq!(0x414fa339, crc32_table(crc32_table(0, &fox[..split]), &fox[split..]));
+        assert_eq!(0x414fa339, crc32_slice_by_8(crc32_slice_by_8(0, &fox[..split]), &fox[split..]));
+    }
+}
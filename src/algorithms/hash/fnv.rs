@@ -0,0 +1,31 @@
+//! The Fowler–Noll–Vo hash is a very simple non-cryptographic hash function, which is fast for short keys.
+//!
+//! This implementation is based on the FNV-1a variant, which XORs each byte into the hash before multiplying.
+static OFFSET_BASIS_32: u32 = 0x811c9dc5u32;
+static PRIME_32: u32 = 0x01000193u32;
+static OFFSET_BASIS_64: u64 = 0xcbf29ce484222325u64;
+static PRIME_64: u64 = 0x100000001b3u64;
+
+pub fn fnv1a_32(data: &[u8]) -> u32 {
+    data.iter().fold(OFFSET_BASIS_32, |hash, &byte| (hash ^ byte as u32).wrapping_mul(PRIME_32))
+}
+
+pub fn fnv1a_64(data: &[u8]) -> u64 {
+    fnv1a_64_seed(data, 0)
+}
+
+/// FNV-1a has no seed of its own, so the seed is XORed into the offset basis. A seed of 0 gives the standard hash.
+pub fn fnv1a_64_seed(data: &[u8], seed: u64) -> u64 {
+    data.iter().fold(OFFSET_BASIS_64 ^ seed, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME_64))
+}
+
+#[test]
+fn basic_tests() {
+    assert_eq!(0x811c9dc5, fnv1a_32("".as_bytes()));
+    assert_eq!(0xe40c292c, fnv1a_32("a".as_bytes()));
+    assert_eq!(0xbf9cf968, fnv1a_32("foobar".as_bytes()));
+    assert_eq!(0xcbf29ce484222325, fnv1a_64("".as_bytes()));
+    assert_eq!(0xaf63dc4c8601ec8c, fnv1a_64("a".as_bytes()));
+    assert_eq!(0x85944171f73967e8, fnv1a_64("foobar".as_bytes()));
+    assert!(fnv1a_64_seed("foobar".as_bytes(), 1) != fnv1a_64("foobar".as_bytes()));
+}
@@ -0,0 +1,114 @@
+//! A family of non-cryptographic hash functions behind a common `HashFunction` trait.
+//!
+//! Data structures that hash their values, such as `BloomFilter` or `CountMinSketch`, are parameterized by a
+//! `HashFunction`, so that the hash function can be chosen to fit the data. They default to `Murmur3`, or to
+//! `Murmur3X64` where they need 64 bits of hash.
+//!
+//! Hash function | Output | Notes
+//! :------------ | :----: | :----
+//! Murmur3 | 32 bits | Good distribution, fast on any platform.
+//! Murmur3X64 | 64 bits | The lower half of murmur3_x64_128.
+//! Fnv1a | 64 bits | Very fast for short keys, weaker distribution.
+//! XxHash32 | 32 bits | Very fast for long keys.
+//! XxHash64 | 64 bits | Very fast for long keys on 64-bit platforms.
+//! CityHash64 | 64 bits | Fast for short and long keys on 64-bit platforms.
+//!
+//! The `crc32` module provides CRC32 checksums, which are not a `HashFunction`: a CRC is linear, so the CRCs of the
+//! same data with two different seeds differ by a value that does not depend on the data.
+use algorithms::murmur::{murmur3_32_seed, murmur3_x64_128, split_128};
+
+pub mod fnv;
+pub mod xxhash;
+pub mod crc32;
+pub mod city;
+
+/// A seeded hash function of byte strings.
+///
+/// Different seeds should give independent hashes of the same data. Hash functions with a 32-bit output return it
+/// in the lower bits.
+pub trait HashFunction {
+    fn hash(&self, data: &[u8], seed: u32) -> u64;
+}
+
+/// The 32-bit murmur3 hash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Murmur3;
+
+impl HashFunction for Murmur3 {
+    #[inline]
+    fn hash(&self, data: &[u8], seed: u32) -> u64 {
+        murmur3_32_seed(data, seed) as u64
+    }
+}
+
+/// The lower 64 bits of the 128-bit murmur3 hash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Murmur3X64;
+
+impl HashFunction for Murmur3X64 {
+    #[inline]
+    fn hash(&self, data: &[u8], seed: u32) -> u64 {
+        split_128(murmur3_x64_128(data, seed)).0
+    }
+}
+
+/// The 64-bit FNV-1a hash, with the seed XORed into the offset basis.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fnv1a;
+
+impl HashFunction for Fnv1a {
+    #[inline]
+    fn hash(&self, data: &[u8], seed: u32) -> u64 {
+        fnv::fnv1a_64_seed(data, seed as u64)
+    }
+}
+
+/// The 32-bit xxHash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XxHash32;
+
+impl HashFunction for XxHash32 {
+    #[inline]
+    fn hash(&self, data: &[u8], seed: u32) -> u64 {
+        xxhash::xxhash32(data, seed) as u64
+    }
+}
+
+/// The 64-bit xxHash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XxHash64;
+
+impl HashFunction for XxHash64 {
+    #[inline]
+    fn hash(&self, data: &[u8], seed: u32) -> u64 {
+        xxhash::xxhash64(data, seed as u64)
+    }
+}
+
+/// The 64-bit CityHash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CityHash64;
+
+impl HashFunction for CityHash64 {
+    #[inline]
+    fn hash(&self, data: &[u8], seed: u32) -> u64 {
+        city::city_hash64_seed(data, seed as u64)
+    }
+}
+
+#[test]
+fn basic_tests() {
+    let data = "I will not buy this record, it is scratched.".as_bytes();
+
+    assert_eq!(2832214938, Murmur3.hash(data, 0));
+    assert_eq!(split_128(murmur3_x64_128(data, 0)).0, Murmur3X64.hash(data, 0));
+    assert_eq!(0xaf63dc4c8601ec8c, Fnv1a.hash("a".as_bytes(), 0));
+    assert_eq!(0x32d153ff, XxHash32.hash("abc".as_bytes(), 0));
+    assert_eq!(0x44bc2cf5ad770999, XxHash64.hash("abc".as_bytes(), 0));
+    assert_eq!(city::city_hash64_seed(data, 0), CityHash64.hash(data, 0));
+
+    let functions: [&dyn HashFunction; 6] = [&Murmur3, &Murmur3X64, &Fnv1a, &XxHash32, &XxHash64, &CityHash64];
+    for function in functions.iter() {
+        assert!(function.hash(data, 0) != function.hash(data, 1));
+    }
+}
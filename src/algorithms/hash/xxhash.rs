@@ -0,0 +1,153 @@
+//! xxHash is an extremely fast non-cryptographic hash function, which processes the input in independent lanes.
+//!
+//! This implementation provides the 32-bit and 64-bit variants, XXH32 and XXH64.
+static PRIME32_1: u32 = 0x9e3779b1u32;
+static PRIME32_2: u32 = 0x85ebca77u32;
+static PRIME32_3: u32 = 0xc2b2ae3du32;
+static PRIME32_4: u32 = 0x27d4eb2fu32;
+static PRIME32_5: u32 = 0x165667b1u32;
+
+static PRIME64_1: u64 = 0x9e3779b185ebca87u64;
+static PRIME64_2: u64 = 0xc2b2ae3d27d4eb4fu64;
+static PRIME64_3: u64 = 0x165667b19e3779f9u64;
+static PRIME64_4: u64 = 0x85ebca77c2b2ae63u64;
+static PRIME64_5: u64 = 0x27d4eb2f165667c5u64;
+
+fn get_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn get_u64(data: &[u8]) -> u64 {
+    ((get_u32(&data[4..]) as u64) << 32) | get_u32(data) as u64
+}
+
+#[inline]
+fn round_32(accumulator: u32, input: u32) -> u32 {
+    accumulator.wrapping_add(input.wrapping_mul(PRIME32_2)).rotate_left(13).wrapping_mul(PRIME32_1)
+}
+
+pub fn xxhash32(data: &[u8], seed: u32) -> u32 {
+    let length = data.len();
+    let mut index = 0;
+
+    let mut hash = if length >= 16 {
+        let mut v1 = seed.wrapping_add(PRIME32_1).wrapping_add(PRIME32_2);
+        let mut v2 = seed.wrapping_add(PRIME32_2);
+        let mut v3 = seed;
+        let mut v4 = seed.wrapping_sub(PRIME32_1);
+
+        while index + 16 <= length {
+            v1 = round_32(v1, get_u32(&data[index..]));
+            v2 = round_32(v2, get_u32(&data[index + 4..]));
+            v3 = round_32(v3, get_u32(&data[index + 8..]));
+            v4 = round_32(v4, get_u32(&data[index + 12..]));
+            index += 16;
+        }
+
+        v1.rotate_left(1).wrapping_add(v2.rotate_left(7)).wrapping_add(v3.rotate_left(12)).wrapping_add(v4.rotate_left(18))
+    } else {
+        seed.wrapping_add(PRIME32_5)
+    };
+
+    hash = hash.wrapping_add(length as u32);
+
+    while index + 4 <= length {
+        hash = hash.wrapping_add(get_u32(&data[index..]).wrapping_mul(PRIME32_3));
+        hash = hash.rotate_left(17).wrapping_mul(PRIME32_4);
+        index += 4;
+    }
+
+    for &byte in data[index..].iter() {
+        hash = hash.wrapping_add((byte as u32).wrapping_mul(PRIME32_5));
+        hash = hash.rotate_left(11).wrapping_mul(PRIME32_1);
+    }
+
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(PRIME32_2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(PRIME32_3);
+    hash ^= hash >> 16;
+
+    hash
+}
+
+#[inline]
+fn round_64(accumulator: u64, input: u64) -> u64 {
+    accumulator.wrapping_add(input.wrapping_mul(PRIME64_2)).rotate_left(31).wrapping_mul(PRIME64_1)
+}
+
+#[inline]
+fn merge_round_64(hash: u64, value: u64) -> u64 {
+    (hash ^ round_64(0, value)).wrapping_mul(PRIME64_1).wrapping_add(PRIME64_4)
+}
+
+pub fn xxhash64(data: &[u8], seed: u64) -> u64 {
+    let length = data.len();
+    let mut index = 0;
+
+    let mut hash = if length >= 32 {
+        let mut v1 = seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2);
+        let mut v2 = seed.wrapping_add(PRIME64_2);
+        let mut v3 = seed;
+        let mut v4 = seed.wrapping_sub(PRIME64_1);
+
+        while index + 32 <= length {
+            v1 = round_64(v1, get_u64(&data[index..]));
+            v2 = round_64(v2, get_u64(&data[index + 8..]));
+            v3 = round_64(v3, get_u64(&data[index + 16..]));
+            v4 = round_64(v4, get_u64(&data[index + 24..]));
+            index += 32;
+        }
+
+        let mut hash = v1.rotate_left(1)
+            .wrapping_add(v2.rotate_left(7))
+            .wrapping_add(v3.rotate_left(12))
+            .wrapping_add(v4.rotate_left(18));
+
+        hash = merge_round_64(hash, v1);
+        hash = merge_round_64(hash, v2);
+        hash = merge_round_64(hash, v3);
+        merge_round_64(hash, v4)
+    } else {
+        seed.wrapping_add(PRIME64_5)
+    };
+
+    hash = hash.wrapping_add(length as u64);
+
+    while index + 8 <= length {
+        hash ^= round_64(0, get_u64(&data[index..]));
+        hash = hash.rotate_left(27).wrapping_mul(PRIME64_1).wrapping_add(PRIME64_4);
+        index += 8;
+    }
+
+    if index + 4 <= length {
+        hash ^= (get_u32(&data[index..]) as u64).wrapping_mul(PRIME64_1);
+        hash = hash.rotate_left(23).wrapping_mul(PRIME64_2).wrapping_add(PRIME64_3);
+        index += 4;
+    }
+
+    for &byte in data[index..].iter() {
+        hash ^= (byte as u64).wrapping_mul(PRIME64_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME64_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME64_3);
+    hash ^= hash >> 32;
+
+    hash
+}
+
+#[test]
+fn basic_tests() {
+    let spam = "Nobody inspects the spammish repetition".as_bytes();
+
+    assert_eq!(0x02cc5d05, xxhash32("".as_bytes(), 0));
+    assert_eq!(0x32d153ff, xxhash32("abc".as_bytes(), 0));
+    assert_eq!(0xe2293b2f, xxhash32(spam, 0));
+    assert_eq!(0xef46db3751d8e999, xxhash64("".as_bytes(), 0));
+    assert_eq!(0x44bc2cf5ad770999, xxhash64("abc".as_bytes(), 0));
+    assert_eq!(0xfbcea83c8a378bf1, xxhash64(spam, 0));
+}
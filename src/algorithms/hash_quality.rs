@@ -0,0 +1,270 @@
+//! Statistical tests of the quality of hash functions.
+//!
+//! A good non-cryptographic hash function should satisfy the following:
+//!
+//! - **Avalanche**: flipping a single input bit flips every output bit with a probability of 1/2. The strict
+//!   avalanche criterion (SAC) demands this for every pair of input and output bits, not just on average.
+//! - **Bit independence**: the output bits flipped by a single input bit are uncorrelated with each other.
+//! - **Distribution**: the hashes of a key set spread evenly over a number of buckets, which is measured with
+//!   a chi-square test.
+//! - **Collisions**: a key set has about as many colliding hashes as a random function would give.
+//!
+//! `analyze()` runs all of the tests and returns a `HashQualityReport` with the results.
+use std::cmp::min;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use algorithms::hash::HashFunction;
+
+/// The seed of the key generator, so that the tests are reproducible.
+static KEY_SEED: [u32; 4] = [0x193a6754, 0xa8a7d469, 0x97830e05, 0x113ba7bb];
+
+/// The results of the tests run by `analyze()`.
+#[derive(Clone, Debug)]
+pub struct HashQualityReport {
+    /// The number of output bits of the hash function.
+    pub output_bits: u32,
+    /// The mean probability of an output bit flipping when a single input bit is flipped. Ideally `0.5`.
+    pub avalanche: f64,
+    /// The largest deviation from `0.5` of the flip probability of any input bit and output bit pair.
+    pub max_avalanche_bias: f64,
+    /// The largest absolute correlation between the flips of any two output bits.
+    pub max_bit_correlation: f64,
+    /// The chi-square statistic of the hashes of the key set over the buckets.
+    pub chi_square: f64,
+    /// The number of buckets of the chi-square test.
+    pub buckets: usize,
+    /// The number of colliding hashes in the key set.
+    pub collisions: usize,
+    /// The number of collisions a random function is expected to give for the key set.
+    pub expected_collisions: f64,
+}
+
+impl HashQualityReport {
+    /// Returns by how many standard deviations the chi-square statistic deviates from its expected value.
+    ///
+    /// With `buckets - 1` degrees of freedom, the statistic has a mean of `df` and a variance of `2 * df`. A good
+    /// hash function should stay within a few standard deviations in either direction; a value far below zero is
+    /// suspicious as well, as it means the keys spread more evenly than random ones would.
+    pub fn chi_square_deviation(&self) -> f64 {
+        let df = (self.buckets - 1) as f64;
+        (self.chi_square - df) / (2.0 * df).sqrt()
+    }
+}
+
+/// Returns the mask of the lower `output_bits` bits.
+fn mask(output_bits: u32) -> u64 {
+    if output_bits >= 64 {
+        !0u64
+    } else {
+        (1u64 << output_bits) - 1
+    }
+}
+
+/// Returns `count` keys with the little-endian bytes of `0, 1, 2, ...`, which are notoriously similar to each
+/// other.
+pub fn sequential_keys(count: u32) -> Vec<Vec<u8>> {
+    (0 .. count).map(|i| vec![i as u8, (i >> 8) as u8, (i >> 16) as u8, (i >> 24) as u8]).collect()
+}
+
+/// Returns `count` pseudo-random keys of `length` bytes.
+pub fn random_keys(count: usize, length: usize) -> Vec<Vec<u8>> {
+    let mut rng = XorShiftRng::from_seed(KEY_SEED);
+    (0 .. count).map(|_| (0 .. length).map(|_| rng.gen()).collect()).collect()
+}
+
+/// Returns the avalanche matrix, where `matrix[i][j]` is the probability of output bit `j` flipping when input bit
+/// `i` is flipped, over `samples` random keys of `key_length` bytes.
+pub fn avalanche_matrix<H: HashFunction>(
+    hash_function: &H,
+    output_bits: u32,
+    key_length: usize,
+    samples: usize
+) -> Vec<Vec<f64>> {
+    let mask = mask(output_bits);
+    let mut flips = vec![vec![0u32; output_bits as usize]; key_length * 8];
+
+    for mut key in random_keys(samples, key_length) {
+        let hash = hash_function.hash(&key, 0) & mask;
+
+        for (input_bit, row) in flips.iter_mut().enumerate() {
+            key[input_bit / 8] ^= 1 << (input_bit % 8);
+            let difference = (hash_function.hash(&key, 0) & mask) ^ hash;
+            key[input_bit / 8] ^= 1 << (input_bit % 8);
+
+            for (output_bit, count) in row.iter_mut().enumerate() {
+                *count += ((difference >> output_bit) & 1) as u32;
+            }
+        }
+    }
+
+    flips.iter().map(|row| row.iter().map(|&count| count as f64 / samples as f64).collect()).collect()
+}
+
+/// Returns the largest absolute correlation between the flips of any two output bits, when single input bits of
+/// `samples` random keys of `key_length` bytes are flipped.
+///
+/// The correlation of two bits is the phi coefficient of their flips, which is `0` for independent bits.
+pub fn bit_independence<H: HashFunction>(
+    hash_function: &H,
+    output_bits: u32,
+    key_length: usize,
+    samples: usize
+) -> f64 {
+    let bits = output_bits as usize;
+    let mask = mask(output_bits);
+    let mut flips = vec![0u32; bits];
+    let mut both_flips = vec![vec![0u32; bits]; bits];
+    let mut trials = 0u32;
+
+    for mut key in random_keys(samples, key_length) {
+        let hash = hash_function.hash(&key, 0) & mask;
+
+        for input_bit in 0 .. key_length * 8 {
+            key[input_bit / 8] ^= 1 << (input_bit % 8);
+            let difference = (hash_function.hash(&key, 0) & mask) ^ hash;
+            key[input_bit / 8] ^= 1 << (input_bit % 8);
+            trials += 1;
+
+            let flipped: Vec<usize> = (0 .. bits).filter(|&bit| (difference >> bit) & 1 == 1).collect();
+            for (i, &a) in flipped.iter().enumerate() {
+                flips[a] += 1;
+                for &b in flipped[i + 1 ..].iter() {
+                    both_flips[a][b] += 1;
+                }
+            }
+        }
+    }
+
+    let mut max_correlation = 0f64;
+    for a in 0 .. bits {
+        for b in a + 1 .. bits {
+            let pa = flips[a] as f64 / trials as f64;
+            let pb = flips[b] as f64 / trials as f64;
+            let pab = both_flips[a][b] as f64 / trials as f64;
+            let variance = pa * (1.0 - pa) * pb * (1.0 - pb);
+
+            // A bit that always or never flips is perfectly correlated with whatever it is compared to.
+            let correlation = if variance == 0.0 {
+                1.0
+            } else {
+                (pab - pa * pb) / variance.sqrt()
+            };
+
+            if correlation.abs() > max_correlation {
+                max_correlation = correlation.abs();
+            }
+        }
+    }
+
+    max_correlation
+}
+
+/// Returns the chi-square statistic of the hashes of the keys over `buckets` buckets.
+///
+/// The bucket of a key is its hash modulo the number of buckets.
+pub fn chi_square<H: HashFunction>(hash_function: &H, keys: &[Vec<u8>], buckets: usize) -> f64 {
+    let mut counts = vec![0u32; buckets];
+    for key in keys {
+        counts[(hash_function.hash(key, 0) % buckets as u64) as usize] += 1;
+    }
+
+    let expected = keys.len() as f64 / buckets as f64;
+    counts.iter().fold(0f64, |acc, &count| acc + (count as f64 - expected).powi(2) / expected)
+}
+
+/// Returns the number of keys whose hash equals the hash of an earlier key.
+pub fn collisions<H: HashFunction>(hash_function: &H, output_bits: u32, keys: &[Vec<u8>]) -> usize {
+    let mask = mask(output_bits);
+    let mut hashes: Vec<u64> = keys.iter().map(|key| hash_function.hash(key, 0) & mask).collect();
+    hashes.sort();
+
+    hashes.windows(2).filter(|pair| pair[0] == pair[1]).count()
+}
+
+/// Returns the number of collisions a random function with `output_bits` bits is expected to give for `keys` keys:
+/// `n - 2^b + 2^b * (1 - 1/2^b)^n`.
+pub fn expected_collisions(output_bits: u32, keys: usize) -> f64 {
+    let space = 2f64.powi(min(output_bits, 64) as i32);
+    let n = keys as f64;
+
+    // (1 - 1/2^b)^n loses all precision for large spaces, where the birthday approximation n^2 / 2^(b+1) is exact
+    // enough.
+    if space > 1e12 {
+        n * n / (2.0 * space)
+    } else {
+        n - space + space * (1.0 - 1.0 / space).powf(n)
+    }
+}
+
+/// Runs all the tests on the hash function, which has `output_bits` bits of output.
+///
+/// The avalanche and bit independence tests use 2000 random 8-byte keys. The chi-square and collision tests use
+/// 100000 sequential keys, which are harder to distribute than random ones, and 1024 buckets.
+pub fn analyze<H: HashFunction>(hash_function: &H, output_bits: u32) -> HashQualityReport {
+    let matrix = avalanche_matrix(hash_function, output_bits, 8, 2000);
+    let cells = (matrix.len() * output_bits as usize) as f64;
+    let avalanche = matrix.iter().flat_map(|row| row.iter()).fold(0f64, |acc, &p| acc + p) / cells;
+    let max_avalanche_bias = matrix.iter().flat_map(|row| row.iter()).fold(0f64, |acc, &p| acc.max((p - 0.5).abs()));
+
+    let keys = sequential_keys(100_000);
+    let buckets = 1024;
+
+    HashQualityReport {
+        output_bits: output_bits,
+        avalanche: avalanche,
+        max_avalanche_bias: max_avalanche_bias,
+        max_bit_correlation: bit_independence(hash_function, output_bits, 8, 2000),
+        chi_square: chi_square(hash_function, &keys, buckets),
+        buckets: buckets,
+        collisions: collisions(hash_function, output_bits, &keys),
+        expected_collisions: expected_collisions(output_bits, keys.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use algorithms::hash::{HashFunction, Murmur3, Fnv1a};
+
+    /// A hash function that returns the first four bytes of the data, which distributes sequential keys perfectly
+    /// but does not avalanche at all.
+    struct Identity;
+
+    impl HashFunction for Identity {
+        fn hash(&self, data: &[u8], _seed: u32) -> u64 {
+            data.iter().take(4).enumerate().fold(0u64, |acc, (i, &byte)| acc | (byte as u64) << (i * 8))
+        }
+    }
+
+    #[test]
+    fn murmur3() {
+        let report = analyze(&Murmur3, 32);
+
+        assert!((report.avalanche - 0.5).abs() < 0.01, "{:?}", report);
+        assert!(report.max_avalanche_bias < 0.05, "{:?}", report);
+        assert!(report.max_bit_correlation < 0.05, "{:?}", report);
+        assert!(report.chi_square_deviation().abs() < 4.0, "{:?}", report);
+        assert!(report.collisions as f64 <= report.expected_collisions * 5.0 + 5.0, "{:?}", report);
+    }
+
+    #[test]
+    fn detects_bad_hash_functions() {
+        let report = analyze(&Identity, 32);
+
+        assert!(report.avalanche < 0.1, "{:?}", report);
+        assert!(report.max_avalanche_bias > 0.4, "{:?}", report);
+        assert!(report.chi_square_deviation() < -4.0, "{:?}", report);
+        assert_eq!(0, report.collisions);
+
+        // FNV-1a mixes the last byte poorly into the upper bits, which the strict avalanche criterion notices.
+        let report = analyze(&Fnv1a, 64);
+        assert!(report.max_avalanche_bias > 0.2, "{:?}", report);
+    }
+
+    #[test]
+    fn expected_collisions_of_random_functions() {
+        assert_eq!(0.0, expected_collisions(32, 1));
+        assert!((expected_collisions(8, 256) - 93.99).abs() < 0.01);
+        assert!((expected_collisions(32, 100_000) - 1.164).abs() < 0.001);
+        assert!((expected_collisions(64, 1 << 32) - 0.5).abs() < 1e-9);
+    }
+}
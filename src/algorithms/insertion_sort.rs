@@ -1,3 +1,6 @@
+use std::cmp::Ordering;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
 /// Efficient sorting against small or already sorted sets.
 ///
 /// Insertion sort is inefficient against large sets. It requires no additional memory and is stable.
@@ -7,13 +10,38 @@
 ///
 /// Insertion sort can also sort sets as it receives them.
 pub fn insertion_sort<A: Ord>(data: &mut [A]) {
+    insertion_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with insertion sort, using the comparator function to order the elements.
+pub fn insertion_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    insertion_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with insertion sort, ordering the elements by the keys the function extracts from them.
+pub fn insertion_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    insertion_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with insertion sort, reporting the comparisons and swaps to the observer.
+pub fn insertion_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    insertion_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with insertion sort, using the comparator function to order the elements, and reporting the
+/// swaps to the observer.
+pub fn insertion_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
     match data.len() {
         0 | 1 => (),
         size => {
             for i in 1 .. size {
                 let mut x = i;
-                while x > 0 && &data[x - 1] > &data[x] {
+                while x > 0 && compare(&data[x - 1], &data[x]) == Ordering::Greater {
                     data.swap(x, x - 1);
+                    observer.swapped(data, x, x - 1);
                     x -= 1;
                 }
             }
@@ -24,6 +52,7 @@ pub fn insertion_sort<A: Ord>(data: &mut [A]) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use algorithms::sort_stats::{SortStats, SortTrace};
 
     #[test]
     fn test_insertion_sort() {
@@ -31,4 +60,41 @@ mod tests {
         insertion_sort(data.as_mut_slice());
         assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
     }
+
+    #[test]
+    fn test_insertion_sort_by() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        insertion_sort_by(data.as_mut_slice(), |a, b| b.cmp(a));
+        assert_eq!([16, 9, 8, 5, 4, 3, 2, 1, 0], data.as_slice());
+
+        let mut data = vec![2.5, -1.0, 0.5, 10.0];
+        insertion_sort_by(data.as_mut_slice(), |a: &f64, b| a.partial_cmp(b).unwrap());
+        assert_eq!([-1.0, 0.5, 2.5, 10.0], data.as_slice());
+    }
+
+    #[test]
+    fn test_insertion_sort_by_key() {
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 2)];
+        insertion_sort_by_key(data.as_mut_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("d", 2), ("a", 3)], data.as_slice());
+    }
+
+    /// Every swap moves an element past one element it is greater than, so the number of swaps is the number of
+    /// inversions.
+    #[test]
+    fn test_insertion_sort_observed() {
+        let mut data = vec![3, 1, 2];
+        let trace = SortTrace::new(&data);
+        insertion_sort_observed(data.as_mut_slice(), &trace);
+
+        assert_eq!(vec![vec![3, 1, 2], vec![1, 3, 2], vec![1, 2, 3]], trace.states());
+        assert_eq!(2, trace.stats().swaps());
+        assert_eq!(3, trace.stats().comparisons());
+
+        let mut data: Vec<u32> = (0 .. 100).rev().collect();
+        let stats = SortStats::new();
+        insertion_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!(100 * 99 / 2, stats.swaps());
+        assert_eq!(0, stats.writes());
+    }
 }
@@ -0,0 +1,325 @@
+use std::cmp::Ordering;
+use std::mem;
+use algorithms::insertion_sort::insertion_sort_observed_by;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// Slices up to this length are sorted with insertion sort, which is faster for short slices.
+static INSERTION_SORT_THRESHOLD: usize = 16usize;
+
+/// Slices of at least this length take the pivot from the ninther instead of the median of three.
+static NINTHER_THRESHOLD: usize = 128usize;
+
+/// Efficient sorting against large sets, with an `O(n log n)` worst case.
+///
+/// Introsort (Musser, 1997) is a quicksort that guards against its own worst cases, in the spirit of
+/// pattern-defeating quicksort:
+///
+/// - The pivot is the median of three elements, or for large slices the ninther, the median of three medians of
+///   three. This avoids the `O(n^2)` behavior on sorted and reversed data.
+/// - The partitioning is three-way, into the elements less than, equal to and greater than the pivot. The equal
+///   elements are never looked at again, so data with many duplicates sorts in `O(n)` to `O(n log n)`.
+/// - When the recursion gets deeper than `2 log n`, the slice is sorted with heapsort instead, which bounds the
+///   worst case to `O(n log n)`.
+/// - Short slices are sorted with insertion sort.
+/// - Only the smaller side of a partition is sorted recursively, and the larger one iteratively, which bounds the
+///   stack depth to `O(log n)`.
+///
+/// Introsort is not stable.
+pub fn intro_sort<A: Ord>(data: &mut [A]) {
+    intro_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with introsort, using the comparator function to order the elements.
+pub fn intro_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    intro_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with introsort, ordering the elements by the keys the function extracts from them.
+pub fn intro_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    intro_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with introsort, reporting the comparisons and swaps to the observer.
+pub fn intro_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    intro_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with introsort, using the comparator function to order the elements, and reporting the swaps to
+/// the observer.
+pub fn intro_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
+    let depth_limit = 2 * (mem::size_of::<usize>() * 8 - data.len().leading_zeros() as usize);
+    sort(data, &mut compare, observer, depth_limit);
+}
+
+fn sort<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(mut data: &mut [A], compare: &mut F, observer: &O, mut depth_limit: usize) {
+    loop {
+        if data.len() <= INSERTION_SORT_THRESHOLD {
+            insertion_sort_observed_by(data, |a, b| compare(a, b), observer);
+            return;
+        }
+
+        if depth_limit == 0 {
+            heap_sort(data, compare, observer);
+            return;
+        }
+        depth_limit -= 1;
+
+        let pivot = choose_pivot(data, compare);
+        data.swap(0, pivot);
+        observer.swapped(data, 0, pivot);
+        let (less, greater) = partition(data, compare, observer);
+
+        let (left, rest) = { data }.split_at_mut(less);
+        let right = &mut rest[greater - less ..];
+
+        if left.len() < right.len() {
+            sort(left, compare, observer, depth_limit);
+            data = right;
+        } else {
+            sort(right, compare, observer, depth_limit);
+            data = left;
+        }
+    }
+}
+
+/// Returns the index of the median of the elements at the three indices.
+fn median_of_three<A, F: FnMut(&A, &A) -> Ordering>(data: &[A], a: usize, b: usize, c: usize, compare: &mut F) -> usize {
+    let (a, b) = if compare(&data[b], &data[a]) == Ordering::Less { (b, a) } else { (a, b) };
+
+    // Now a <= b, so the median is b if c is greater, and the greater of a and c otherwise.
+    if compare(&data[c], &data[b]) != Ordering::Less {
+        b
+    } else if compare(&data[c], &data[a]) == Ordering::Less {
+        a
+    } else {
+        c
+    }
+}
+
+/// Returns the index of the pivot: the median of the first, middle and last elements, or for large slices the
+/// ninther (Tukey, 1978).
+fn choose_pivot<A, F: FnMut(&A, &A) -> Ordering>(data: &[A], compare: &mut F) -> usize {
+    let (last, middle) = (data.len() - 1, data.len() / 2);
+
+    if data.len() < NINTHER_THRESHOLD {
+        median_of_three(data, 0, middle, last, compare)
+    } else {
+        let step = data.len() / 8;
+        let a = median_of_three(data, 0, step, 2 * step, compare);
+        let b = median_of_three(data, middle - step, middle, middle + step, compare);
+        let c = median_of_three(data, last - 2 * step, last - step, last, compare);
+        median_of_three(data, a, b, c, compare)
+    }
+}
+
+/// Partitions the data around the pivot at index 0 into the elements less than, equal to and greater than it
+/// (Dijkstra's Dutch national flag).
+///
+/// Returns `(less, greater)`, such that `data[..less]` is less than the pivot, `data[less..greater]` equals it and
+/// `data[greater..]` is greater. The swaps are reported to the observer.
+pub fn partition<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], compare: &mut F, observer: &O) -> (usize, usize) {
+    let (mut less, mut index, mut greater) = (0, 1, data.len());
+
+    // `data[less]` is always equal to the pivot, because the equal elements are never empty.
+    while index < greater {
+        match compare(&data[index], &data[less]) {
+            Ordering::Less => {
+                data.swap(less, index);
+                observer.swapped(data, less, index);
+                less += 1;
+                index += 1;
+            },
+            Ordering::Greater => {
+                greater -= 1;
+                data.swap(index, greater);
+                observer.swapped(data, index, greater);
+            },
+            Ordering::Equal => index += 1,
+        }
+    }
+
+    (less, greater)
+}
+
+/// Sorts the data with heapsort, in `O(n log n)` without any additional memory.
+fn heap_sort<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], compare: &mut F, observer: &O) {
+    /// Moves the element at the root down until both of its children are less than it.
+    fn sift_down<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut root: usize, compare: &mut F, observer: &O) {
+        loop {
+            let mut child = 2 * root + 1;
+            if child >= data.len() {
+                return;
+            }
+
+            if child + 1 < data.len() && compare(&data[child], &data[child + 1]) == Ordering::Less {
+                child += 1;
+            }
+
+            if compare(&data[root], &data[child]) != Ordering::Less {
+                return;
+            }
+
+            data.swap(root, child);
+            observer.swapped(data, root, child);
+            root = child;
+        }
+    }
+
+    for root in (0 .. data.len() / 2).rev() {
+        sift_down(data, root, compare, observer);
+    }
+
+    for end in (1 .. data.len()).rev() {
+        data.swap(0, end);
+        observer.swapped(data, 0, end);
+        sift_down(&mut data[..end], 0, compare, observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use test_helpers::random_data;
+    use algorithms::quick_sort::quick_sort;
+    use algorithms::sort_stats::{SortStats, SortTrace};
+
+    static BENCH_SIZE: u32 = 10_000u32;
+
+    fn assert_sorts(data: Vec<u32>) {
+        let mut expected = data.clone();
+        expected.sort();
+
+        let mut sorted = data;
+        intro_sort(sorted.as_mut_slice());
+        assert_eq!(expected, sorted);
+    }
+
+    #[test]
+    fn test_intro_sort() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        intro_sort(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
+
+        for &size in [0, 1, 2, 16, 17, 127, 128, 1_000, 10_000].iter() {
+            assert_sorts(random_data(size, u32::MAX));
+            assert_sorts(random_data(size, 3));
+            assert_sorts((0 .. size as u32).collect());
+            assert_sorts((0 .. size as u32).rev().collect());
+            assert_sorts(vec![7; size]);
+            assert_sorts((0 .. size as u32).map(|i| if i % 2 == 0 { i } else { size as u32 - i }).collect());
+        }
+    }
+
+    #[test]
+    fn test_intro_sort_by() {
+        let mut data = random_data(1_000, 100);
+        intro_sort_by(data.as_mut_slice(), |a, b| b.cmp(a));
+        assert!(data.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        let mut data = vec![2.5, -1.0, 0.5, 10.0];
+        intro_sort_by(data.as_mut_slice(), |a: &f64, b| a.partial_cmp(b).unwrap());
+        assert_eq!([-1.0, 0.5, 2.5, 10.0], data.as_slice());
+
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        intro_sort_by_key(data.as_mut_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("a", 3), ("d", 4)], data.as_slice());
+    }
+
+    #[test]
+    fn test_heap_sort_fallback() {
+        let mut data = random_data(1_000, 50);
+        let mut expected = data.clone();
+        expected.sort();
+
+        sort(data.as_mut_slice(), &mut |a: &u32, b: &u32| a.cmp(b), &NoObserver, 0);
+        assert_eq!(expected, data);
+    }
+
+    /// All-equal data is a single partition, rather than `n` levels of recursion as with Lomuto partitioning.
+    #[test]
+    fn test_all_equal_comparisons() {
+        let mut data = vec![1u32; 100_000];
+        let mut comparisons = 0;
+        intro_sort_by(data.as_mut_slice(), |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        assert!(comparisons < 200_000, "{} comparisons", comparisons);
+    }
+
+    #[test]
+    fn test_intro_sort_observed() {
+        let mut data = random_data(1_000, u32::MAX);
+        let stats = SortStats::new();
+        intro_sort_observed(data.as_mut_slice(), &stats);
+        assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(stats.comparisons() < 20_000, "{} comparisons", stats.comparisons());
+        assert!(stats.swaps() < 20_000, "{} swaps", stats.swaps());
+
+        let mut data = random_data(50, 10);
+        let trace = SortTrace::new(&data);
+        intro_sort_observed(data.as_mut_slice(), &trace);
+        assert_eq!(Some(&data), trace.states().last());
+        assert_eq!(trace.stats().swaps() + 1, trace.states().len());
+    }
+
+    #[bench]
+    fn intro_sort_random(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE as usize, u32::MAX);
+        b.iter(|| intro_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn intro_sort_sorted(b: &mut Bencher) {
+        let data: Vec<u32> = (0 .. BENCH_SIZE).collect();
+        b.iter(|| intro_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn intro_sort_reversed(b: &mut Bencher) {
+        let data: Vec<u32> = (0 .. BENCH_SIZE).rev().collect();
+        b.iter(|| intro_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn intro_sort_all_equal(b: &mut Bencher) {
+        let data = vec![7u32; BENCH_SIZE as usize];
+        b.iter(|| intro_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn quick_sort_random(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE as usize, u32::MAX);
+        b.iter(|| quick_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn quick_sort_sorted(b: &mut Bencher) {
+        let data: Vec<u32> = (0 .. BENCH_SIZE).collect();
+        b.iter(|| quick_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn quick_sort_reversed(b: &mut Bencher) {
+        let data: Vec<u32> = (0 .. BENCH_SIZE).rev().collect();
+        b.iter(|| quick_sort(data.clone().as_mut_slice()))
+    }
+
+    /// Quicksort is quadratic on all-equal data, so this uses a tenth of the data of the other benchmarks.
+    #[bench]
+    fn quick_sort_all_equal(b: &mut Bencher) {
+        let data = vec![7u32; BENCH_SIZE as usize / 10];
+        b.iter(|| quick_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn std_sort_unstable_random(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE as usize, u32::MAX);
+        b.iter(|| data.clone().sort_unstable())
+    }
+}
@@ -1,20 +1,58 @@
+use std::cmp::Ordering;
+use std::ptr;
+use algorithms::insertion_sort::insertion_sort_observed_by;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// Slices up to this length are sorted with insertion sort, which is faster for short slices.
+static INSERTION_SORT_THRESHOLD: usize = 16usize;
+
 /// Efficient sorting against large sets. Requires `O(n)` aux. space.
 ///
 /// This divide-and-conquer sorting algorithm, while inefficient with memory use, performs
 /// `O(n log n)` in average, worst and best case scenarios even against large sets of data.
 pub fn merge_sort<A: Ord + Clone>(data: &[A]) -> Vec<A> {
-    fn divide<A: Ord + Clone>(data: &[A]) -> Vec<A> {
+    merge_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with merge sort, using the comparator function to order the elements.
+pub fn merge_sort_by<A: Clone, F: FnMut(&A, &A) -> Ordering>(data: &[A], compare: F) -> Vec<A> {
+    merge_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with merge sort, reporting the comparisons and writes to the observer.
+///
+/// Every element cloned into a new vector is a write, so the data is written once for every level of the
+/// recursion. The writes are to new vectors rather than the data, so a `SortTrace` only counts them.
+pub fn merge_sort_observed<A: Ord + Clone, O: SortObserver<A>>(data: &[A], observer: &O) -> Vec<A> {
+    merge_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with merge sort, using the comparator function to order the elements, and reporting the writes
+/// to the observer.
+pub fn merge_sort_observed_by<A: Clone, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &[A], mut compare: F, observer: &O) -> Vec<A> {
+    fn divide<A: Clone, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &[A], compare: &mut F, observer: &O) -> Vec<A> {
         match data.len() {
             0 => vec![],
-            1 => vec![data[0].clone()],
+            1 => {
+                let result = vec![data[0].clone()];
+                observer.wrote(&result, 0 .. 1);
+                result
+            },
             size => {
                 let middle = size / 2;
-                conquer(divide(&data[..middle]), divide(&data[middle..]))
+                let left = divide(&data[..middle], compare, observer);
+                let right = divide(&data[middle..], compare, observer);
+                let result = conquer(left, right, compare);
+                observer.wrote(&result, 0 .. result.len());
+                result
             }
         }
     }
 
-    fn conquer<A: Ord + Clone>(left: Vec<A>, right: Vec<A>) -> Vec<A> {
+    fn conquer<A: Clone, F: FnMut(&A, &A) -> Ordering>(left: Vec<A>, right: Vec<A>, compare: &mut F) -> Vec<A> {
         let mut left_index = 0;
         let mut right_index = 0;
 
@@ -23,8 +61,9 @@ pub fn merge_sort<A: Ord + Clone>(data: &[A]) -> Vec<A> {
 
         let mut result = Vec::with_capacity(left_size + right_size);
 
+        // Taking from the left on ties keeps equal elements in their original order.
         while left_index < left_size && right_index < right_size {
-            if left[left_index] < right[right_index] {
+            if compare(&left[left_index], &right[right_index]) != Ordering::Greater {
                 result.push(left[left_index].clone());
                 left_index += 1;
             } else {
@@ -39,12 +78,154 @@ pub fn merge_sort<A: Ord + Clone>(data: &[A]) -> Vec<A> {
         result
     }
 
-    divide(data)
+    divide(data, &mut compare, observer)
+}
+
+/// Sorts the data with merge sort, ordering the elements by the keys the function extracts from them.
+pub fn merge_sort_by_key<A: Clone, K: Ord, F: FnMut(&A) -> K>(data: &[A], mut key: F) -> Vec<A> {
+    merge_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data in place with a stable merge sort. Requires `O(n)` aux. space.
+///
+/// Unlike `merge_sort`, the elements are moved rather than cloned, and a single scratch buffer of `n / 2`
+/// elements is allocated for all the merges. Equal elements keep their original order.
+pub fn merge_sort_in_place<A: Ord>(data: &mut [A]) {
+    merge_sort_in_place_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data in place with a stable merge sort, using the comparator function to order the elements.
+pub fn merge_sort_in_place_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    merge_sort_in_place_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data in place with a stable merge sort, reporting the comparisons, swaps and writes to the observer.
+///
+/// A merge writes the elements it moves back from the buffer or within the data, but not the ones that are already
+/// in place. The moves to the buffer are not counted.
+pub fn merge_sort_in_place_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    merge_sort_in_place_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data in place with a stable merge sort, using the comparator function to order the elements, and
+/// reporting the swaps and writes to the observer.
+pub fn merge_sort_in_place_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
+    fn sort<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], buffer: *mut A, compare: &mut F, observer: &O) {
+        let size = data.len();
+        if size <= INSERTION_SORT_THRESHOLD {
+            insertion_sort_observed_by(data, |a, b| compare(a, b), observer);
+            return;
+        }
+
+        let middle = size / 2;
+        sort(&mut data[..middle], buffer, compare, observer);
+        sort(&mut data[middle..], buffer, compare, observer);
+
+        // The halves are already in order, which makes sorted input `O(n)`.
+        if compare(&data[middle - 1], &data[middle]) != Ordering::Greater {
+            return;
+        }
+
+        let written = unsafe { merge(data, middle, buffer, compare) };
+        observer.wrote(data, 0 .. written);
+    }
+
+    let mut buffer: Vec<A> = Vec::with_capacity(data.len() / 2);
+    sort(data, buffer.as_mut_ptr(), &mut compare, observer);
+}
+
+/// Sorts the data in place with a stable merge sort, ordering the elements by the keys the function extracts from
+/// them.
+pub fn merge_sort_in_place_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    merge_sort_in_place_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Merges the sorted halves `data[..middle]` and `data[middle..]` in place, keeping equal elements in their original
+/// order. Requires `O(middle)` aux. space.
+pub fn merge_in_place_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], middle: usize, mut compare: F) {
+    assert!(middle <= data.len(), "the middle {} is out of bounds for length {}", middle, data.len());
+    if middle == 0 || middle == data.len() || compare(&data[middle - 1], &data[middle]) != Ordering::Greater {
+        return;
+    }
+
+    let mut buffer: Vec<A> = Vec::with_capacity(middle);
+    unsafe { merge(data, middle, buffer.as_mut_ptr(), &mut compare); }
+}
+
+/// Merges the sorted halves `data[..middle]` and `data[middle..]`.
+///
+/// The left half is moved to the buffer, and merged back into the data from the front. The next free position
+/// is always before the next element of the right half, so no element is overwritten before it is moved.
+///
+/// Returns the number of elements written to the data, which are the ones before the rest of the right half.
+unsafe fn merge<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], middle: usize, buffer: *mut A, compare: &mut F) -> usize {
+    let destination = data.as_mut_ptr();
+    let mut right = destination.add(middle);
+    let right_end = destination.add(data.len());
+
+    ptr::copy_nonoverlapping(destination, buffer, middle);
+    let mut hole = MergeHole {
+        start: buffer,
+        end: buffer.add(middle),
+        destination: destination,
+    };
+
+    while hole.start < hole.end && right < right_end {
+        // Taking from the left on ties keeps equal elements in their original order.
+        let next = if compare(&*right, &*hole.start) == Ordering::Less {
+            let next = right;
+            right = right.add(1);
+            next
+        } else {
+            let next = hole.start;
+            hole.start = hole.start.add(1);
+            next
+        };
+
+        ptr::copy_nonoverlapping(next, hole.destination, 1);
+        hole.destination = hole.destination.add(1);
+    }
+
+    // Dropping the hole moves the rest of the left half back. The rest of the right half is already in place.
+    right.offset_from(destination) as usize
+}
+
+/// The elements of the left half that are still in the buffer.
+///
+/// The elements are moved back to the data when the hole is dropped, also when the comparator panics, so that
+/// every element is in the data exactly once.
+struct MergeHole<A> {
+    start: *mut A,
+    end: *mut A,
+    destination: *mut A,
+}
+
+impl<A> Drop for MergeHole<A> {
+    fn drop(&mut self) {
+        unsafe {
+            let remaining = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.destination, remaining);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::panic;
+    use std::rc::Rc;
+    use algorithms::sort_stats::{SortStats, SortTrace};
+    use rand::Rng;
+    use test_helpers::rng;
+
+    /// Returns pairs of a key with many duplicates and the original position.
+    fn keyed_data(size: usize) -> Vec<(u32, usize)> {
+        let mut rng = rng();
+        (0 .. size).map(|i| (rng.gen_range(0, 10), i)).collect()
+    }
 
     #[test]
     fn test_merge_sort() {
@@ -52,4 +233,144 @@ mod tests {
         data = merge_sort(data.as_mut_slice());
         assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
     }
+
+    #[test]
+    fn test_merge_sort_by() {
+        let data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        assert_eq!([16, 9, 8, 5, 4, 3, 2, 1, 0], merge_sort_by(data.as_slice(), |a, b| b.cmp(a)).as_slice());
+
+        let data = vec![2.5, -1.0, 0.5, 10.0];
+        let sorted = merge_sort_by(data.as_slice(), |a: &f64, b| a.partial_cmp(b).unwrap());
+        assert_eq!([-1.0, 0.5, 2.5, 10.0], sorted.as_slice());
+    }
+
+    #[test]
+    fn test_merge_sort_by_key() {
+        let data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        let sorted = merge_sort_by_key(data.as_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("a", 3), ("d", 4)], sorted.as_slice());
+    }
+
+    #[test]
+    fn test_merge_sort_is_stable() {
+        let data = keyed_data(1_000);
+        let mut expected = data.clone();
+        expected.sort_by_key(|&(key, _)| key);
+
+        assert_eq!(expected, merge_sort_by_key(data.as_slice(), |&(key, _)| key));
+    }
+
+    /// Every level of the recursion clones all of the data once.
+    #[test]
+    fn test_merge_sort_observed() {
+        let data = vec![9, 8, 1, 5, 3, 16, 2, 0];
+        let stats = SortStats::new();
+        assert_eq!(vec![0, 1, 2, 3, 5, 8, 9, 16], merge_sort_observed(data.as_slice(), &stats));
+        assert_eq!(8 * 4, stats.writes());
+        assert_eq!(0, stats.swaps());
+        assert!(stats.comparisons() <= 8 * 3, "{} comparisons", stats.comparisons());
+
+        // The merged vectors are not a part of the data, so the trace has no states but the first.
+        let trace = SortTrace::new(&data);
+        merge_sort_observed(data.as_slice(), &trace);
+        assert_eq!(vec![data.clone()], trace.states());
+        assert_eq!(8 * 4, trace.stats().writes());
+    }
+
+    #[test]
+    fn test_merge_sort_in_place() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        merge_sort_in_place(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
+
+        for &size in [0, 1, 2, 16, 17, 100, 1_000].iter() {
+            let mut data: Vec<u32> = keyed_data(size).iter().map(|&(key, i)| key * 1_000 + (i as u32 * 7919) % 1_000).collect();
+            let mut expected = data.clone();
+            expected.sort();
+            merge_sort_in_place(data.as_mut_slice());
+            assert_eq!(expected, data);
+        }
+
+        let mut data = vec![2.5, -1.0, 0.5, 10.0];
+        merge_sort_in_place_by(data.as_mut_slice(), |a: &f64, b| b.partial_cmp(a).unwrap());
+        assert_eq!([10.0, 2.5, 0.5, -1.0], data.as_slice());
+    }
+
+    #[test]
+    fn test_merge_sort_in_place_is_stable() {
+        for &size in [10, 100, 1_000, 10_000].iter() {
+            let mut data = keyed_data(size);
+            let mut expected = data.clone();
+            expected.sort_by_key(|&(key, _)| key);
+
+            merge_sort_in_place_by_key(data.as_mut_slice(), |&(key, _)| key);
+            assert_eq!(expected, data);
+        }
+    }
+
+    #[test]
+    fn test_merge_in_place_by() {
+        let mut data = vec![(1, 'a'), (3, 'a'), (5, 'a'), (0, 'b'), (3, 'b'), (6, 'b')];
+        merge_in_place_by(data.as_mut_slice(), 3, |a, b| a.0.cmp(&b.0));
+        assert_eq!([(0, 'b'), (1, 'a'), (3, 'a'), (3, 'b'), (5, 'a'), (6, 'b')], data.as_slice());
+
+        for &middle in [0, 6].iter() {
+            let mut data = vec![1, 2, 3, 4, 5, 6];
+            merge_in_place_by(data.as_mut_slice(), middle, |a, b| a.cmp(b));
+            assert_eq!([1, 2, 3, 4, 5, 6], data.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_merge_sort_in_place_observed() {
+        // Sorted data is never merged.
+        let mut data: Vec<u32> = (0 .. 1_000).collect();
+        let stats = SortStats::new();
+        merge_sort_in_place_observed(data.as_mut_slice(), &stats);
+        assert_eq!(0, stats.writes());
+        assert!(stats.comparisons() < 2_000, "{} comparisons", stats.comparisons());
+
+        let mut data: Vec<u32> = keyed_data(100).iter().map(|&(key, _)| key).collect();
+        let trace = SortTrace::new(&data);
+        merge_sort_in_place_observed(data.as_mut_slice(), &trace);
+        assert!(trace.stats().writes() > 0);
+        assert_eq!(Some(&data), trace.states().last());
+        assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    /// Elements that cannot be cloned are moved.
+    #[test]
+    fn test_merge_sort_in_place_without_clone() {
+        struct Element(u32);
+
+        let mut data: Vec<Element> = (0 .. 100).map(|i| Element((i * 37) % 100)).collect();
+        merge_sort_in_place_by_key(data.as_mut_slice(), |element| element.0);
+        assert!(data.iter().enumerate().all(|(i, element)| element.0 == i as u32));
+    }
+
+    /// A panicking comparator leaves every element in the data exactly once.
+    #[test]
+    fn test_merge_sort_in_place_panic_safety() {
+        let value = Rc::new(());
+        let mut data: Vec<(u32, Rc<()>)> = (0 .. 100).map(|i| ((i * 37) % 100, value.clone())).collect();
+        let mut comparisons = 0;
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            merge_sort_in_place_by(data.as_mut_slice(), |a, b| {
+                comparisons += 1;
+                if comparisons == 500 {
+                    panic!("comparator failure");
+                }
+                a.0.cmp(&b.0)
+            });
+        }));
+
+        assert!(result.is_err());
+        let mut keys: Vec<u32> = data.iter().map(|element| element.0).collect();
+        keys.sort();
+        assert_eq!((0 .. 100).collect::<Vec<u32>>(), keys);
+
+        drop(data);
+        assert_eq!(1, Rc::strong_count(&value));
+    }
 }
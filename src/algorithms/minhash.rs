@@ -0,0 +1,277 @@
+//! MinHash estimates the Jaccard similarity of two sets from short signatures (Broder, 1997).
+//!
+//! Each of the `k` signature values is the minimum hash over the elements of the set, using a differently seeded
+//! hash function as a random permutation. Two sets share a signature value with a probability equal to their
+//! Jaccard similarity, `|A ∩ B| / |A ∪ B|`.
+//!
+//! Locality-sensitive hashing splits the signatures into bands, and considers two sets a candidate pair when
+//! any band is identical. This finds near-duplicates without comparing every pair of signatures.
+use std::collections::HashMap;
+use algorithms::hash::{HashFunction, Murmur3};
+use algorithms::murmur::murmur3_32_seed;
+
+/// Returns the overlapping byte shingles (k-grams) of the given size.
+///
+/// Data shorter than the shingle size yields itself as the only shingle, and empty data yields no shingles.
+pub fn shingles(data: &[u8], size: usize) -> Vec<&[u8]> {
+    if size == 0 {
+        panic!("the shingle size has to be at least 1");
+    }
+
+    match data.len() {
+        0 => vec![],
+        length if length < size => vec![data],
+        _ => data.windows(size).collect(),
+    }
+}
+
+/// Returns the exact Jaccard similarity of the shingle sets of the two byte strings.
+pub fn jaccard_similarity(a: &[u8], b: &[u8], size: usize) -> f64 {
+    let mut a_shingles = shingles(a, size);
+    let mut b_shingles = shingles(b, size);
+    a_shingles.sort();
+    a_shingles.dedup();
+    b_shingles.sort();
+    b_shingles.dedup();
+
+    let intersection = a_shingles.iter().filter(|shingle| b_shingles.binary_search(shingle).is_ok()).count();
+    let union = a_shingles.len() + b_shingles.len() - intersection;
+
+    if union == 0 { 1.0 } else { intersection as f64 / union as f64 }
+}
+
+/// Computes MinHash signatures of `permutations` values.
+///
+/// The signature values are the lower 32 bits of the hashes.
+pub struct MinHash<H = Murmur3> {
+    permutations: u32,
+    hash_function: H,
+}
+
+impl MinHash {
+    /// Constructs a new MinHash with the given number of permutations.
+    ///
+    /// The standard error of the similarity estimate is `1 / sqrt(permutations)`.
+    pub fn new(permutations: u32) -> MinHash {
+        MinHash::with_hasher(permutations, Murmur3)
+    }
+}
+
+impl<H: HashFunction> MinHash<H> {
+    /// Constructs a new MinHash with the given number of permutations, that uses the given hash function.
+    pub fn with_hasher(permutations: u32, hash_function: H) -> MinHash<H> {
+        MinHash {
+            permutations: permutations,
+            hash_function: hash_function,
+        }
+    }
+
+    /// Returns the signature of the set of elements. Duplicate elements do not affect the signature.
+    pub fn signature<'a, I: IntoIterator<Item = &'a [u8]>>(&self, elements: I) -> Vec<u32> {
+        let mut signature = vec![u32::MAX; self.permutations as usize];
+
+        for element in elements {
+            for (seed, value) in signature.iter_mut().enumerate() {
+                let hash = self.hash_function.hash(element, seed as u32) as u32;
+                if hash < *value {
+                    *value = hash;
+                }
+            }
+        }
+
+        signature
+    }
+
+    /// Returns the signature of the byte shingles of the data.
+    pub fn signature_of_shingles(&self, data: &[u8], size: usize) -> Vec<u32> {
+        self.signature(shingles(data, size))
+    }
+
+    /// Returns the number of permutations.
+    pub fn permutations(&self) -> u32 {
+        self.permutations
+    }
+}
+
+/// Estimates the Jaccard similarity of two sets from their signatures.
+pub fn estimate_similarity(a: &[u32], b: &[u32]) -> f64 {
+    if a.len() != b.len() {
+        panic!("cannot compare signatures of lengths {} and {}", a.len(), b.len());
+    }
+
+    if a.is_empty() {
+        return 0.0;
+    }
+
+    let equal = a.iter().zip(b.iter()).filter(|&(x, y)| x == y).count();
+    equal as f64 / a.len() as f64
+}
+
+/// A locality-sensitive hashing index of MinHash signatures.
+///
+/// The signatures are split into `bands` bands of `rows` values each. Two signatures become candidates when at
+/// least one of their bands is identical, which happens with a probability of `1 - (1 - s^rows)^bands` for
+/// sets of similarity `s`.
+pub struct LshIndex {
+    bands: usize,
+    rows: usize,
+    buckets: Vec<HashMap<u32, Vec<usize>>>,
+}
+
+impl LshIndex {
+    /// Constructs a new index for signatures of `bands * rows` values. Both have to be positive.
+    pub fn new(bands: usize, rows: usize) -> LshIndex {
+        if bands == 0 || rows == 0 {
+            panic!("an lsh index needs a positive number of bands and rows, got {} bands of {} rows", bands, rows);
+        }
+
+        LshIndex {
+            bands: bands,
+            rows: rows,
+            buckets: (0 .. bands).map(|_| HashMap::new()).collect(),
+        }
+    }
+
+    /// Returns the similarity at which sets become candidates with a probability of about one half,
+    /// `(1 / bands)^(1 / rows)`.
+    pub fn threshold(&self) -> f64 {
+        (1.0 / self.bands as f64).powf(1.0 / self.rows as f64)
+    }
+
+    fn band_hashes(&self, signature: &[u32]) -> Vec<u32> {
+        if signature.len() != self.bands * self.rows {
+            panic!(
+                "the signature has {} values, but the index expects {}",
+                signature.len(),
+                self.bands * self.rows
+            );
+        }
+
+        signature.chunks(self.rows).enumerate().map(|(band, values)| {
+            let bytes: Vec<u8> = values.iter().flat_map(|&value| {
+                vec![value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+            }).collect();
+
+            murmur3_32_seed(&bytes, band as u32)
+        }).collect()
+    }
+
+    /// Adds a signature to the index under the given id.
+    pub fn insert(&mut self, id: usize, signature: &[u32]) {
+        let hashes = self.band_hashes(signature);
+
+        for (band, hash) in hashes.into_iter().enumerate() {
+            self.buckets[band].entry(hash).or_default().push(id);
+        }
+    }
+
+    /// Returns the ids of the indexed signatures that share a band with the given signature, in ascending order.
+    pub fn query(&self, signature: &[u32]) -> Vec<usize> {
+        let mut candidates: Vec<usize> = self.band_hashes(signature).into_iter().enumerate()
+            .filter_map(|(band, hash)| self.buckets[band].get(&hash))
+            .flat_map(|ids| ids.iter().cloned())
+            .collect();
+
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Returns every pair of ids that share a band, with the smaller id first, in ascending order.
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+
+        for bucket in self.buckets.iter().flat_map(|band| band.values()) {
+            for (i, &a) in bucket.iter().enumerate() {
+                for &b in bucket[i + 1 ..].iter() {
+                    if a != b {
+                        pairs.push(if a < b { (a, b) } else { (b, a) });
+                    }
+                }
+            }
+        }
+
+        pairs.sort();
+        pairs.dedup();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DOCUMENTS: [&str; 4] = [
+        "The quick brown fox jumps over the lazy dog near the river bank.",
+        "The quick brown fox jumped over the lazy dog near the river bank!",
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod.",
+        "The quick brown fox jumps over the lazy dog near the river bank.",
+    ];
+
+    #[test]
+    fn test_shingles() {
+        assert_eq!(vec![&b"abc"[..], &b"bcd"[..]], shingles(b"abcd", 3));
+        assert_eq!(vec![&b"ab"[..]], shingles(b"ab", 3));
+        assert!(shingles(b"", 3).is_empty());
+    }
+
+    #[test]
+    fn test_estimate_similarity() {
+        let minhash = MinHash::new(256);
+        let signatures: Vec<Vec<u32>> = DOCUMENTS.iter()
+            .map(|document| minhash.signature_of_shingles(document.as_bytes(), 4))
+            .collect();
+
+        assert_eq!(1.0, estimate_similarity(&signatures[0], &signatures[3]));
+
+        for &(a, b) in [(0, 1), (0, 2), (1, 2)].iter() {
+            let exact = jaccard_similarity(DOCUMENTS[a].as_bytes(), DOCUMENTS[b].as_bytes(), 4);
+            let estimate = estimate_similarity(&signatures[a], &signatures[b]);
+            assert!((exact - estimate).abs() < 0.15, "estimated {} for {}", estimate, exact);
+        }
+    }
+
+    #[test]
+    fn test_lsh_index() {
+        let minhash = MinHash::new(100);
+        let mut index = LshIndex::new(20, 5);
+        assert!((index.threshold() - 0.549).abs() < 0.001);
+
+        for (id, document) in DOCUMENTS.iter().enumerate() {
+            index.insert(id, &minhash.signature_of_shingles(document.as_bytes(), 4));
+        }
+
+        let pairs = index.candidate_pairs();
+        assert!(pairs.contains(&(0, 1)));
+        assert!(pairs.contains(&(0, 3)));
+        assert!(pairs.contains(&(1, 3)));
+        assert!(!pairs.iter().any(|&(a, b)| a == 2 || b == 2));
+
+        assert_eq!(vec![2], index.query(&minhash.signature_of_shingles(DOCUMENTS[2].as_bytes(), 4)));
+    }
+
+    #[test]
+    fn test_lsh_index_invalid_parameters() {
+        use std::panic;
+
+        for &(bands, rows) in [(0, 4), (4, 0), (0, 0)].iter() {
+            assert!(panic::catch_unwind(|| LshIndex::new(bands, rows)).is_err(), "{} bands of {} rows", bands, rows);
+        }
+    }
+
+    #[test]
+    fn test_hash_functions() {
+        use algorithms::hash::XxHash32;
+
+        let minhash = MinHash::with_hasher(256, XxHash32);
+        let signatures: Vec<Vec<u32>> = DOCUMENTS.iter()
+            .map(|document| minhash.signature_of_shingles(document.as_bytes(), 4))
+            .collect();
+
+        assert!(signatures[0] != MinHash::new(256).signature_of_shingles(DOCUMENTS[0].as_bytes(), 4));
+        assert_eq!(1.0, estimate_similarity(&signatures[0], &signatures[3]));
+
+        let exact = jaccard_similarity(DOCUMENTS[0].as_bytes(), DOCUMENTS[1].as_bytes(), 4);
+        assert!((exact - estimate_similarity(&signatures[0], &signatures[1])).abs() < 0.15);
+    }
+}
@@ -3,3 +3,17 @@ pub mod selection_sort;
 pub mod merge_sort;
 pub mod quick_sort;
 pub mod murmur;
+pub mod minhash;
+pub mod hash;
+pub mod hash_quality;
+pub mod consistent_hash;
+pub mod intro_sort;
+pub mod tim_sort;
+pub mod radix_sort;
+pub mod parallel_sort;
+pub mod external_sort;
+pub mod quick_select;
+pub mod sort_stats;
+pub mod shell_sort;
+pub mod comb_sort;
+pub mod cycle_sort;
@@ -1,6 +1,11 @@
 //! The murmur hash is a relatively fast non-cryptographic hash function for platforms with efficient multiplication.
 //!
-//! This implementation is based on the murmurhash3 variant.
+//! This implementation is based on the murmurhash3 variant. Besides the 32-bit hash, it provides the two 128-bit
+//! variants: `murmur3_x64_128` which is fastest on 64-bit platforms, and `murmur3_x86_128` which is fastest on 32-bit
+//! platforms. The two produce different hashes.
+use std::cmp::min;
+use std::hash::{BuildHasher, Hasher};
+
 static C1: u32 = 0xcc9e2d51u32;
 static C2: u32 = 0x1b873593u32;
 static R1: u32 = 15u32;
@@ -18,51 +23,306 @@ pub fn murmur3_32_seed(data: &[u8], seed: u32) -> u32 {
 
     let n_blocks = length / 4;
     for i in 0 .. n_blocks {
-        let mut k = get_u32(&data[(i * 4) as usize..]);
-        k = k.wrapping_mul(C1);
-        k = (k << R1) | (k >> (32 - R1));
-        k = k.wrapping_mul(C2);
-
-        hash ^= k;
-        hash = ((hash << R2) | (hash >> (32 - R2))).wrapping_mul(M).wrapping_add(N);
+        hash = mix_block(hash, get_u32(&data[(i * 4) as usize..]));
     }
 
     let tail = &data[(n_blocks * 4) as usize..];
-    let remainder = length & 3;
-    let mut k1 = 0u32;
+    finish_32(hash, tail, length)
+}
+
+#[inline]
+fn mix_k1(mut k1: u32) -> u32 {
+    k1 = k1.wrapping_mul(C1);
+    k1 = k1.rotate_left(R1);
+    k1.wrapping_mul(C2)
+}
 
-    if remainder == 3 {
-        k1 ^= (tail[2] as u32) << 16;
+#[inline]
+fn mix_block(hash: u32, k: u32) -> u32 {
+    let hash = hash ^ mix_k1(k);
+    hash.rotate_left(R2).wrapping_mul(M).wrapping_add(N)
+}
+
+/// Mixes in the remaining zero to three bytes, and finalizes the hash.
+#[inline]
+fn finish_32(mut hash: u32, tail: &[u8], length: u32) -> u32 {
+    if !tail.is_empty() {
+        hash ^= mix_k1(get_tail_u32(tail));
     }
 
-    if remainder >= 2 {
-        k1 ^= (tail[1] as u32) << 8
+    fmix32(hash ^ length)
+}
+
+/// A streaming murmur3_32 hasher.
+///
+/// The data may be written in any number of parts. Partial 4-byte blocks are buffered between the writes, so the
+/// result of `finish_32` is identical to `murmur3_32_seed` of the concatenated data.
+///
+/// `finish` spreads the 32-bit hash over all 64 bits with the 64-bit finalizer of murmur3, since hash tables may take
+/// their bits from either end of the hash.
+#[derive(Clone)]
+pub struct Murmur3Hasher {
+    hash: u32,
+    length: u32,
+    tail: [u8; 4],
+    tail_length: usize,
+}
+
+impl Murmur3Hasher {
+    /// Creates a new hasher with a seed of 0.
+    pub fn new() -> Murmur3Hasher {
+        Murmur3Hasher::with_seed(0)
+    }
+
+    /// Creates a new hasher with the given seed.
+    pub fn with_seed(seed: u32) -> Murmur3Hasher {
+        Murmur3Hasher {
+            hash: seed,
+            length: 0,
+            tail: [0u8; 4],
+            tail_length: 0,
+        }
     }
 
-    if remainder >= 1 {
-        k1 ^= tail[0] as u32;
+    /// Returns the murmur3_32 hash of the data written so far.
+    pub fn finish_32(&self) -> u32 {
+        finish_32(self.hash, &self.tail[..self.tail_length], self.length)
+    }
+}
 
-        k1 = k1.wrapping_mul(C1);
-        k1 = (k1 << R1) | (k1 >> (32 - R1));
-        k1 = k1.wrapping_mul(C2);
-        hash ^= k1;
+impl Default for Murmur3Hasher {
+    fn default() -> Murmur3Hasher {
+        Murmur3Hasher::new()
     }
+}
+
+impl Hasher for Murmur3Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut data = bytes;
+        self.length = self.length.wrapping_add(bytes.len() as u32);
 
-    hash ^= length;
+        // Complete the buffered block first.
+        if self.tail_length > 0 {
+            let needed = min(4 - self.tail_length, data.len());
+            self.tail[self.tail_length..self.tail_length + needed].copy_from_slice(&data[..needed]);
+            self.tail_length += needed;
+            data = &data[needed..];
+
+            if self.tail_length < 4 {
+                return;
+            }
+
+            self.hash = mix_block(self.hash, get_u32(&self.tail));
+            self.tail_length = 0;
+        }
+
+        let n_blocks = data.len() / 4;
+        for i in 0 .. n_blocks {
+            self.hash = mix_block(self.hash, get_u32(&data[i * 4..]));
+        }
+
+        let rest = &data[n_blocks * 4..];
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_length = rest.len();
+    }
+
+    fn finish(&self) -> u64 {
+        fmix64(self.finish_32() as u64)
+    }
+}
+
+/// Builds `Murmur3Hasher`s with a fixed seed, e.g. for `std::collections::HashMap`.
+#[derive(Clone, Default)]
+pub struct BuildMurmur3 {
+    seed: u32,
+}
+
+impl BuildMurmur3 {
+    /// Creates a new builder of hashers with the given seed.
+    pub fn new(seed: u32) -> BuildMurmur3 {
+        BuildMurmur3 { seed: seed }
+    }
+}
+
+impl BuildHasher for BuildMurmur3 {
+    type Hasher = Murmur3Hasher;
+
+    fn build_hasher(&self) -> Murmur3Hasher {
+        Murmur3Hasher::with_seed(self.seed)
+    }
+}
+
+fn get_u32(data: &[u8]) -> u32 {
+    ((0xff & (data[3] as u32)) << 24) |
+    ((0xff & (data[2] as u32)) << 16) |
+    ((0xff & (data[1] as u32)) << 8) |
+    (0xff & (data[0] as u32))
+}
+
+fn get_u64(data: &[u8]) -> u64 {
+    ((get_u32(&data[4..]) as u64) << 32) | get_u32(data) as u64
+}
+
+fn fmix32(mut hash: u32) -> u32 {
     hash ^= hash >> 16;
     hash = hash.wrapping_mul(0x85ebca6b);
     hash ^= hash >> 13;
     hash = hash.wrapping_mul(0xc2b2ae35);
     hash ^= hash >> 16;
+    hash
+}
 
+fn fmix64(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^= hash >> 33;
     hash
 }
 
-fn get_u32(data: &[u8]) -> u32 {
-    ((0xff & (data[3] as u32)) << 24) |
-    ((0xff & (data[2] as u32)) << 16) |
-    ((0xff & (data[1] as u32)) << 8) |
-    (0xff & (data[0] as u32))
+/// Reads up to four bytes of the tail into a little-endian `u32`.
+fn get_tail_u32(tail: &[u8]) -> u32 {
+    tail.iter().take(4).enumerate().fold(0u32, |k, (i, &byte)| k ^ ((byte as u32) << (i * 8)))
+}
+
+/// Reads up to eight bytes of the tail into a little-endian `u64`.
+fn get_tail_u64(tail: &[u8]) -> u64 {
+    tail.iter().take(8).enumerate().fold(0u64, |k, (i, &byte)| k ^ ((byte as u64) << (i * 8)))
+}
+
+static C1_X64: u64 = 0x87c37b91114253d5u64;
+static C2_X64: u64 = 0x4cf5ad432745937fu64;
+
+/// The 128-bit murmur3 hash optimized for 64-bit platforms.
+///
+/// The result is the two 64-bit halves `h1` and `h2` of the reference implementation, with `h1` in the lower
+/// bits. Its little-endian bytes are the reference digest.
+pub fn murmur3_x64_128(data: &[u8], seed: u32) -> u128 {
+    let length = data.len();
+    let (mut h1, mut h2) = (seed as u64, seed as u64);
+
+    let n_blocks = length / 16;
+    for i in 0 .. n_blocks {
+        let mut k1 = get_u64(&data[i * 16..]);
+        let mut k2 = get_u64(&data[i * 16 + 8..]);
+
+        k1 = k1.wrapping_mul(C1_X64).rotate_left(31).wrapping_mul(C2_X64);
+        h1 ^= k1;
+        h1 = h1.rotate_left(27).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x52dce729);
+
+        k2 = k2.wrapping_mul(C2_X64).rotate_left(33).wrapping_mul(C1_X64);
+        h2 ^= k2;
+        h2 = h2.rotate_left(31).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x38495ab5);
+    }
+
+    let tail = &data[n_blocks * 16..];
+
+    if tail.len() > 8 {
+        let k2 = get_tail_u64(&tail[8..]).wrapping_mul(C2_X64).rotate_left(33).wrapping_mul(C1_X64);
+        h2 ^= k2;
+    }
+
+    if !tail.is_empty() {
+        let k1 = get_tail_u64(tail).wrapping_mul(C1_X64).rotate_left(31).wrapping_mul(C2_X64);
+        h1 ^= k1;
+    }
+
+    h1 ^= length as u64;
+    h2 ^= length as u64;
+
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+
+    ((h2 as u128) << 64) | h1 as u128
+}
+
+static C1_X86: u32 = 0x239b961bu32;
+static C2_X86: u32 = 0xab0e9789u32;
+static C3_X86: u32 = 0x38b34ae5u32;
+static C4_X86: u32 = 0xa1e38b93u32;
+
+/// The 128-bit murmur3 hash optimized for 32-bit platforms.
+///
+/// The result is the four 32-bit words `h1` to `h4` of the reference implementation, with `h1` in the lowest
+/// bits. Its little-endian bytes are the reference digest.
+pub fn murmur3_x86_128(data: &[u8], seed: u32) -> u128 {
+    let length = data.len();
+    let (mut h1, mut h2, mut h3, mut h4) = (seed, seed, seed, seed);
+
+    let n_blocks = length / 16;
+    for i in 0 .. n_blocks {
+        let block = &data[i * 16..];
+        let k1 = get_u32(block).wrapping_mul(C1_X86).rotate_left(15).wrapping_mul(C2_X86);
+        h1 ^= k1;
+        h1 = h1.rotate_left(19).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x561ccd1b);
+
+        let k2 = get_u32(&block[4..]).wrapping_mul(C2_X86).rotate_left(16).wrapping_mul(C3_X86);
+        h2 ^= k2;
+        h2 = h2.rotate_left(17).wrapping_add(h3).wrapping_mul(5).wrapping_add(0x0bcaa747);
+
+        let k3 = get_u32(&block[8..]).wrapping_mul(C3_X86).rotate_left(17).wrapping_mul(C4_X86);
+        h3 ^= k3;
+        h3 = h3.rotate_left(15).wrapping_add(h4).wrapping_mul(5).wrapping_add(0x96cd1c35);
+
+        let k4 = get_u32(&block[12..]).wrapping_mul(C4_X86).rotate_left(18).wrapping_mul(C1_X86);
+        h4 ^= k4;
+        h4 = h4.rotate_left(13).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x32ac3b17);
+    }
+
+    let tail = &data[n_blocks * 16..];
+
+    if tail.len() > 12 {
+        h4 ^= get_tail_u32(&tail[12..]).wrapping_mul(C4_X86).rotate_left(18).wrapping_mul(C1_X86);
+    }
+
+    if tail.len() > 8 {
+        h3 ^= get_tail_u32(&tail[8..]).wrapping_mul(C3_X86).rotate_left(17).wrapping_mul(C4_X86);
+    }
+
+    if tail.len() > 4 {
+        h2 ^= get_tail_u32(&tail[4..]).wrapping_mul(C2_X86).rotate_left(16).wrapping_mul(C3_X86);
+    }
+
+    if !tail.is_empty() {
+        h1 ^= get_tail_u32(tail).wrapping_mul(C1_X86).rotate_left(15).wrapping_mul(C2_X86);
+    }
+
+    h1 ^= length as u32;
+    h2 ^= length as u32;
+    h3 ^= length as u32;
+    h4 ^= length as u32;
+
+    h1 = h1.wrapping_add(h2).wrapping_add(h3).wrapping_add(h4);
+    h2 = h2.wrapping_add(h1);
+    h3 = h3.wrapping_add(h1);
+    h4 = h4.wrapping_add(h1);
+
+    h1 = fmix32(h1);
+    h2 = fmix32(h2);
+    h3 = fmix32(h3);
+    h4 = fmix32(h4);
+
+    h1 = h1.wrapping_add(h2).wrapping_add(h3).wrapping_add(h4);
+    h2 = h2.wrapping_add(h1);
+    h3 = h3.wrapping_add(h1);
+    h4 = h4.wrapping_add(h1);
+
+    ((h4 as u128) << 96) | ((h3 as u128) << 64) | ((h2 as u128) << 32) | h1 as u128
+}
+
+/// Splits a 128-bit hash into two 64-bit hashes, the lower half first.
+///
+/// The halves are independent enough to be used as two hash functions, e.g. for double hashing
+/// (`h1 + i * h2`) in bloom filters, or as a 64-bit hash and a fingerprint.
+pub fn split_128(hash: u128) -> (u64, u64) {
+    (hash as u64, (hash >> 64) as u64)
 }
 
 #[test]
@@ -76,3 +336,96 @@ fn basic_tests() {
     assert_eq!(1364076727, murmur3_32_seed("".as_bytes(), 1));
     assert_eq!(2832214938, murmur3_32("I will not buy this record, it is scratched.".as_bytes()));
 }
+
+/// Computes the SMHasher verification value: the keys `[]`, `[0]`, `[0, 1]`, ... `[0, .., 254]` are hashed with the
+/// seed `256 - length`, and the concatenated digests are hashed with the seed 0.
+#[cfg(test)]
+fn verification<F: Fn(&[u8], u32) -> Vec<u8>>(hash: F) -> u32 {
+    let key: Vec<u8> = (0 .. 255u8).collect();
+    let mut digests = Vec::new();
+
+    for length in 0 .. 256 {
+        digests.extend(hash(&key[..length], 256 - length as u32));
+    }
+
+    get_u32(&hash(&digests, 0))
+}
+
+#[test]
+fn smhasher_verification() {
+    assert_eq!(0xb0f57ee3, verification(|data, seed| u32_bytes(murmur3_32_seed(data, seed))));
+    assert_eq!(0x6384ba69, verification(|data, seed| u128_bytes(murmur3_x64_128(data, seed))));
+    assert_eq!(0xb3ece62a, verification(|data, seed| u128_bytes(murmur3_x86_128(data, seed))));
+}
+
+#[cfg(test)]
+fn u32_bytes(hash: u32) -> Vec<u8> {
+    vec![hash as u8, (hash >> 8) as u8, (hash >> 16) as u8, (hash >> 24) as u8]
+}
+
+#[cfg(test)]
+fn u128_bytes(hash: u128) -> Vec<u8> {
+    (0 .. 16).map(|i| (hash >> (i * 8)) as u8).collect()
+}
+
+#[test]
+fn tests_128() {
+    // The reference digest of the fox is 6c1b07bc7bbc4be3 47939ac4a93c437a, i.e. the little-endian bytes.
+    let fox = "The quick brown fox jumps over the lazy dog".as_bytes();
+
+    assert_eq!(0, murmur3_x64_128("".as_bytes(), 0));
+    assert_eq!((0xe34bbc7bbc071b6c, 0x7a433ca9c49a9347), split_128(murmur3_x64_128(fox, 0)));
+    assert_eq!(0, murmur3_x86_128("".as_bytes(), 0));
+}
+
+#[test]
+fn streaming() {
+    let data = "I will not buy this record, it is scratched.".as_bytes();
+
+    for split in 0 .. data.len() {
+        for second_split in split .. data.len() {
+            let mut hasher = Murmur3Hasher::with_seed(42);
+            hasher.write(&data[..split]);
+            hasher.write(&data[split..second_split]);
+            hasher.write(&data[second_split..]);
+            assert_eq!(murmur3_32_seed(data, 42), hasher.finish_32());
+        }
+    }
+
+    assert_eq!(0, Murmur3Hasher::new().finish());
+}
+
+#[test]
+fn build_hasher() {
+    use std::collections::HashMap;
+
+    let mut map = HashMap::with_hasher(BuildMurmur3::new(7));
+    map.insert("one", 1);
+    map.insert("two", 2);
+    assert_eq!(Some(&1), map.get("one"));
+    assert_eq!(Some(&2), map.get("two"));
+
+    let mut hasher = BuildMurmur3::new(7).build_hasher();
+    hasher.write(&[1u8, 2, 3]);
+    assert_eq!(fmix64(murmur3_32_seed(&[1u8, 2, 3], 7) as u64), hasher.finish());
+}
+
+#[test]
+fn finish_uses_high_bits() {
+    let hashes: Vec<u64> = (0 .. 1_000u32).map(|i| {
+        let mut hasher = Murmur3Hasher::new();
+        hasher.write_u32(i);
+        hasher.finish()
+    }).collect();
+
+    // Every one of the top 7 bits, e.g. the tag of a SwissTable, is set in about half of the hashes.
+    for bit in 57 .. 64 {
+        let set = hashes.iter().filter(|&&hash| hash & (1 << bit) != 0).count();
+        assert!(set > 400 && set < 600, "bit {} is set in {} hashes", bit, set);
+    }
+
+    let mut tags: Vec<u64> = hashes.iter().map(|&hash| hash >> 57).collect();
+    tags.sort();
+    tags.dedup();
+    assert_eq!(128, tags.len());
+}
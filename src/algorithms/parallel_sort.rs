@@ -0,0 +1,215 @@
+//! Sorts that split the work across threads.
+//!
+//! The data is divided the same way as by the sequential sorts, and the parts are handed to scoped threads until
+//! every thread has a part or the parts are shorter than `SEQUENTIAL_CUTOFF`. Since the division does not depend on
+//! the timing of the threads, the output is deterministic, and equals the output of the sequential sort.
+use std::cmp::Ordering;
+use std::thread;
+use algorithms::merge_sort::{merge_in_place_by, merge_sort_in_place_by};
+use algorithms::quick_sort::{find_pivot, partition, quick_sort_by};
+use algorithms::sort_stats::NoObserver;
+
+/// Slices up to this length are sorted by a single thread, because spawning a thread costs more than it saves.
+pub static SEQUENTIAL_CUTOFF: usize = 8192usize;
+
+/// Sorts the data with a stable merge sort on the given number of threads.
+///
+/// The halves are sorted on separate threads and merged by the thread that split them, so the last merge is
+/// sequential, which limits the speedup to `O(log n)`. The output equals the output of `merge_sort_in_place`.
+///
+/// Panics if the number of threads is zero.
+pub fn parallel_merge_sort<A: Ord + Send>(data: &mut [A], threads: usize) {
+    parallel_merge_sort_by(data, threads, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with a stable merge sort on the given number of threads, using the comparator function to order
+/// the elements.
+pub fn parallel_merge_sort_by<A: Send, F: Fn(&A, &A) -> Ordering + Sync>(data: &mut [A], threads: usize, compare: F) {
+    fn sort<A: Send, F: Fn(&A, &A) -> Ordering + Sync>(data: &mut [A], threads: usize, compare: &F) {
+        if threads == 1 || data.len() <= SEQUENTIAL_CUTOFF {
+            merge_sort_in_place_by(data, compare);
+            return;
+        }
+
+        let middle = data.len() / 2;
+        {
+            let (left, right) = data.split_at_mut(middle);
+            thread::scope(|scope| {
+                scope.spawn(|| sort(left, threads / 2, compare));
+                sort(right, threads - threads / 2, compare);
+            });
+        }
+
+        merge_in_place_by(data, middle, compare);
+    }
+
+    assert!(threads > 0, "the number of threads has to be at least 1");
+    sort(data, threads, &compare);
+}
+
+/// Sorts the data with a stable merge sort on the given number of threads, ordering the elements by the keys the
+/// function extracts from them.
+pub fn parallel_merge_sort_by_key<A: Send, K: Ord, F: Fn(&A) -> K + Sync>(data: &mut [A], threads: usize, key: F) {
+    parallel_merge_sort_by(data, threads, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with quicksort on the given number of threads.
+///
+/// The data is partitioned by the thread that owns it, and the sides are sorted on separate threads. The first
+/// partition is sequential, and the sides are rarely of equal length, so the speedup is lower than with
+/// `parallel_merge_sort`. The output equals the output of `quick_sort`, including its `O(n^2)` worst case.
+///
+/// Panics if the number of threads is zero.
+pub fn parallel_quick_sort<A: Ord + Send>(data: &mut [A], threads: usize) {
+    parallel_quick_sort_by(data, threads, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with quicksort on the given number of threads, using the comparator function to order the
+/// elements.
+pub fn parallel_quick_sort_by<A: Send, F: Fn(&A, &A) -> Ordering + Sync>(data: &mut [A], threads: usize, compare: F) {
+    fn sort<A: Send, F: Fn(&A, &A) -> Ordering + Sync>(data: &mut [A], threads: usize, compare: &F) {
+        if threads == 1 || data.len() <= SEQUENTIAL_CUTOFF {
+            quick_sort_by(data, compare);
+            return;
+        }
+
+        let mut compare_mut = |a: &A, b: &A| compare(a, b);
+        let pivot = find_pivot(data, &mut compare_mut);
+        let pivot = partition(data, pivot, &mut compare_mut, &NoObserver);
+
+        let (left, right) = data.split_at_mut(pivot);
+        let right = &mut right[1..];
+        thread::scope(|scope| {
+            scope.spawn(|| sort(left, threads / 2, compare));
+            sort(right, threads - threads / 2, compare);
+        });
+    }
+
+    assert!(threads > 0, "the number of threads has to be at least 1");
+    sort(data, threads, &compare);
+}
+
+/// Sorts the data with quicksort on the given number of threads, ordering the elements by the keys the function
+/// extracts from them.
+pub fn parallel_quick_sort_by_key<A: Send, K: Ord, F: Fn(&A) -> K + Sync>(data: &mut [A], threads: usize, key: F) {
+    parallel_quick_sort_by(data, threads, |a, b| key(a).cmp(&key(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use rand::Rng;
+    use test_helpers::{random_data, rng};
+    use algorithms::merge_sort::merge_sort_in_place;
+    use algorithms::quick_sort::quick_sort;
+
+    static BENCH_SIZE: usize = 200_000usize;
+
+    #[test]
+    fn test_parallel_merge_sort() {
+        for &threads in [1, 2, 3, 4, 8].iter() {
+            for &size in [0, 1, SEQUENTIAL_CUTOFF, SEQUENTIAL_CUTOFF + 1, 100_000].iter() {
+                let mut data = random_data(size, u32::MAX);
+                let mut expected = data.clone();
+                merge_sort_in_place(expected.as_mut_slice());
+
+                parallel_merge_sort(data.as_mut_slice(), threads);
+                assert_eq!(expected, data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_merge_sort_is_stable() {
+        let mut rng = rng();
+        let mut data: Vec<(u32, usize)> = (0 .. 100_000).map(|i| (rng.gen_range(0, 10), i)).collect();
+        let mut expected = data.clone();
+        expected.sort_by_key(|&(key, _)| key);
+
+        parallel_merge_sort_by_key(data.as_mut_slice(), 4, |&(key, _)| key);
+        assert_eq!(expected, data);
+    }
+
+    #[test]
+    fn test_parallel_quick_sort() {
+        for &threads in [1, 2, 3, 4, 8].iter() {
+            for &size in [0, 1, SEQUENTIAL_CUTOFF, SEQUENTIAL_CUTOFF + 1, 100_000].iter() {
+                let mut data = random_data(size, u32::MAX);
+                let mut expected = data.clone();
+                quick_sort(expected.as_mut_slice());
+
+                parallel_quick_sort(data.as_mut_slice(), threads);
+                assert_eq!(expected, data);
+            }
+        }
+    }
+
+    /// Elements that compare equal end up in the same positions as with the sequential sort.
+    #[test]
+    fn test_parallel_quick_sort_is_deterministic() {
+        let data: Vec<(u32, usize)> = random_data(50_000, 1_000).into_iter().enumerate().map(|(i, key)| (key, i)).collect();
+        let mut expected = data.clone();
+        quick_sort_by(expected.as_mut_slice(), |a, b| a.0.cmp(&b.0));
+
+        for &threads in [2, 4, 8].iter() {
+            let mut sorted = data.clone();
+            parallel_quick_sort_by_key(sorted.as_mut_slice(), threads, |&(key, _)| key);
+            assert_eq!(expected, sorted);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_threads() {
+        parallel_merge_sort(&mut [2, 1], 0);
+    }
+
+    #[bench]
+    fn parallel_merge_sort_1_thread(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_merge_sort(data.clone().as_mut_slice(), 1))
+    }
+
+    #[bench]
+    fn parallel_merge_sort_2_threads(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_merge_sort(data.clone().as_mut_slice(), 2))
+    }
+
+    #[bench]
+    fn parallel_merge_sort_4_threads(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_merge_sort(data.clone().as_mut_slice(), 4))
+    }
+
+    #[bench]
+    fn parallel_merge_sort_8_threads(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_merge_sort(data.clone().as_mut_slice(), 8))
+    }
+
+    #[bench]
+    fn parallel_quick_sort_1_thread(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_quick_sort(data.clone().as_mut_slice(), 1))
+    }
+
+    #[bench]
+    fn parallel_quick_sort_2_threads(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_quick_sort(data.clone().as_mut_slice(), 2))
+    }
+
+    #[bench]
+    fn parallel_quick_sort_4_threads(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_quick_sort(data.clone().as_mut_slice(), 4))
+    }
+
+    #[bench]
+    fn parallel_quick_sort_8_threads(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| parallel_quick_sort(data.clone().as_mut_slice(), 8))
+    }
+}
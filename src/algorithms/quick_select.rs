@@ -0,0 +1,341 @@
+//! Selection of the k-th smallest element without sorting all the data.
+use std::cmp::Ordering;
+use algorithms::insertion_sort::insertion_sort_observed_by;
+use algorithms::intro_sort::{intro_sort_observed_by, partition as partition_three_way};
+use algorithms::quick_sort::{find_pivot, partition};
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// Slices up to this length are sorted with insertion sort.
+static INSERTION_SORT_THRESHOLD: usize = 16usize;
+
+/// The number of partitions that may shrink the slice by less than a quarter before switching to median of medians.
+static BAD_PARTITIONS: usize = 4usize;
+
+/// Reorders the data such that the element at index `k` is the one that would be there if the data were sorted, the
+/// elements before it are less than or equal to it, and the elements after it are greater than or equal to it.
+/// Returns the element at index `k`.
+///
+/// This is introselect (Musser, 1997): quickselect with the pivots of `quick_sort`, which only continues into the
+/// side of the partition that contains index `k`, and performs `O(n)` on average. When too many partitions are
+/// unbalanced, the pivot is chosen with median of medians (Blum et al., 1973) instead, and the elements equal to
+/// the pivot are split off with a three-way partition, which bounds the worst case to `O(n)`.
+///
+/// Panics if `k` is out of bounds.
+pub fn select_nth_unstable<A: Ord>(data: &mut [A], k: usize) -> &mut A {
+    select_nth_unstable_by(data, k, |a, b| a.cmp(b))
+}
+
+/// Reorders the data such that the element at index `k` is in its sorted position, using the comparator function
+/// to order the elements.
+pub fn select_nth_unstable_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], k: usize, compare: F) -> &mut A {
+    select_nth_unstable_observed_by(data, k, compare, &NoObserver)
+}
+
+/// Reorders the data such that the element at index `k` is in its sorted position, ordering the elements by the
+/// keys the function extracts from them.
+pub fn select_nth_unstable_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], k: usize, mut key: F) -> &mut A {
+    select_nth_unstable_by(data, k, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Reorders the data such that the element at index `k` is in its sorted position, reporting the comparisons and
+/// swaps to the observer.
+pub fn select_nth_unstable_observed<'a, A: Ord, O: SortObserver<A>>(data: &'a mut [A], k: usize, observer: &O) -> &'a mut A {
+    select_nth_unstable_observed_by(data, k, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Reorders the data such that the element at index `k` is in its sorted position, using the comparator function
+/// to order the elements, and reporting the swaps to the observer.
+pub fn select_nth_unstable_observed_by<'a, A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(
+    data: &'a mut [A],
+    k: usize,
+    mut compare: F,
+    observer: &O
+) -> &'a mut A {
+    if k >= data.len() {
+        panic!("the index {} is out of bounds for length {}", k, data.len());
+    }
+
+    select(data, k, &mut compare, observer, BAD_PARTITIONS);
+    &mut data[k]
+}
+
+/// Sorts the `k` smallest elements into `data[..k]`, and leaves the rest of the data in an arbitrary order.
+///
+/// The `k` smallest elements are selected with `select_nth_unstable` and then sorted with `intro_sort`, which
+/// performs `O(n + k log k)`.
+pub fn partial_sort<A: Ord>(data: &mut [A], k: usize) {
+    partial_sort_by(data, k, |a, b| a.cmp(b))
+}
+
+/// Sorts the `k` smallest elements into `data[..k]`, using the comparator function to order the elements.
+pub fn partial_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], k: usize, compare: F) {
+    partial_sort_observed_by(data, k, compare, &NoObserver)
+}
+
+/// Sorts the `k` smallest elements into `data[..k]`, ordering the elements by the keys the function extracts from
+/// them.
+pub fn partial_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], k: usize, mut key: F) {
+    partial_sort_by(data, k, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the `k` smallest elements into `data[..k]`, reporting the comparisons and swaps to the observer.
+pub fn partial_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], k: usize, observer: &O) {
+    partial_sort_observed_by(data, k, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the `k` smallest elements into `data[..k]`, using the comparator function to order the elements, and
+/// reporting the swaps to the observer.
+pub fn partial_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(
+    data: &mut [A],
+    k: usize,
+    mut compare: F,
+    observer: &O
+) {
+    if k == 0 {
+        return;
+    }
+
+    if k < data.len() {
+        select(data, k - 1, &mut compare, observer, BAD_PARTITIONS);
+        intro_sort_observed_by(&mut data[..k - 1], compare, observer);
+    } else {
+        intro_sort_observed_by(data, compare, observer);
+    }
+}
+
+/// Returns the median of the data, which is reordered as with `select_nth_unstable`, or `None` if it is empty.
+///
+/// For an even number of elements, this is the lower of the two middle elements.
+pub fn median<A: Ord>(data: &mut [A]) -> Option<&A> {
+    if data.is_empty() {
+        None
+    } else {
+        let middle = (data.len() - 1) / 2;
+        Some(select_nth_unstable(data, middle))
+    }
+}
+
+fn select<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(
+    mut data: &mut [A],
+    mut k: usize,
+    compare: &mut F,
+    observer: &O,
+    mut bad_partitions: usize
+) {
+    loop {
+        if data.len() <= INSERTION_SORT_THRESHOLD {
+            insertion_sort_observed_by(data, |a, b| compare(a, b), observer);
+            return;
+        }
+
+        let length = data.len();
+
+        if bad_partitions == 0 {
+            let pivot = median_of_medians(data, compare, observer);
+            if pivot != 0 {
+                data.swap(0, pivot);
+                observer.swapped(data, 0, pivot);
+            }
+            let (less, greater) = partition_three_way(data, compare, observer);
+
+            if k < less {
+                data = &mut { data }[..less];
+            } else if k < greater {
+                return;
+            } else {
+                k -= greater;
+                data = &mut { data }[greater..];
+            }
+        } else {
+            let pivot = find_pivot(data, compare);
+            let pivot = partition(data, pivot, compare, observer);
+
+            if k < pivot {
+                data = &mut { data }[..pivot];
+            } else if k > pivot {
+                k -= pivot + 1;
+                data = &mut { data }[pivot + 1..];
+            } else {
+                return;
+            }
+
+            if data.len() > length / 4 * 3 {
+                bad_partitions -= 1;
+            }
+        }
+    }
+}
+
+/// Returns the index of a pivot that is greater than at least 30% of the elements and less than at least 30%.
+///
+/// The median of every group of five elements is moved to the front, and the median of those medians is selected
+/// recursively.
+fn median_of_medians<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], compare: &mut F, observer: &O) -> usize {
+    let groups = data.len() / 5;
+
+    // The group `i` starts at `5 * i`, after all the positions up to `i` that the medians are moved to.
+    for i in 0 .. groups {
+        insertion_sort_observed_by(&mut data[5 * i .. 5 * i + 5], |a, b| compare(a, b), observer);
+        if i != 5 * i + 2 {
+            data.swap(i, 5 * i + 2);
+            observer.swapped(data, i, 5 * i + 2);
+        }
+    }
+
+    select(&mut data[..groups], groups / 2, compare, observer, 0);
+    groups / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use test_helpers::random_data;
+    use algorithms::intro_sort::intro_sort;
+    use algorithms::sort_stats::{SortStats, SortTrace};
+
+    static BENCH_SIZE: usize = 100_000usize;
+
+    fn assert_selects(data: Vec<u32>, k: usize, bad_partitions: usize) {
+        let mut expected = data.clone();
+        expected.sort();
+
+        let mut data = data;
+        select(data.as_mut_slice(), k, &mut |a: &u32, b: &u32| a.cmp(b), &NoObserver, bad_partitions);
+        assert_eq!(expected[k], data[k]);
+        assert!(data[..k].iter().all(|x| *x <= data[k]));
+        assert!(data[k..].iter().all(|x| *x >= data[k]));
+    }
+
+    #[test]
+    fn test_select_nth_unstable() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        assert_eq!(5, *select_nth_unstable(data.as_mut_slice(), 5));
+        assert_eq!(16, *select_nth_unstable(data.as_mut_slice(), 8));
+        assert_eq!(0, *select_nth_unstable(data.as_mut_slice(), 0));
+
+        for &size in [1, 16, 17, 100, 10_000].iter() {
+            for &k in [0, size / 3, size / 2, size - 1].iter() {
+                assert_selects(random_data(size, u32::MAX), k, BAD_PARTITIONS);
+                assert_selects(random_data(size, 3), k, BAD_PARTITIONS);
+                assert_selects((0 .. size as u32).rev().collect(), k, BAD_PARTITIONS);
+            }
+        }
+    }
+
+    #[test]
+    fn test_median_of_medians() {
+        for &size in [17, 100, 1_000, 10_000].iter() {
+            for &k in [0, size / 3, size / 2, size - 1].iter() {
+                assert_selects(random_data(size, u32::MAX), k, 0);
+                assert_selects(random_data(size, 3), k, 0);
+                assert_selects(vec![7; size], k, 0);
+            }
+        }
+    }
+
+    /// All-equal data makes every partition of `quick_sort` unbalanced, so it falls back to median of medians.
+    #[test]
+    fn test_select_is_linear() {
+        let mut data = vec![1u32; 100_000];
+        let mut comparisons = 0;
+        select_nth_unstable_by(data.as_mut_slice(), 50_000, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        assert!(comparisons < 1_000_000, "{} comparisons", comparisons);
+    }
+
+    #[test]
+    fn test_select_observed() {
+        let mut data = random_data(10_000, u32::MAX);
+        let mut expected = data.clone();
+        expected.sort();
+
+        let stats = SortStats::new();
+        assert_eq!(expected[5_000], *select_nth_unstable_observed(data.as_mut_slice(), 5_000, &stats));
+        assert!(stats.comparisons() < 100_000, "{} comparisons", stats.comparisons());
+        assert!(stats.swaps() > 0);
+
+        // Partial sorting takes far fewer comparisons than sorting everything.
+        let mut data = random_data(10_000, u32::MAX);
+        let stats = SortStats::new();
+        partial_sort_observed(data.as_mut_slice(), 10, &stats);
+        assert_eq!(expected[..10], data[..10]);
+        assert!(stats.comparisons() < 10_000 * 13, "{} comparisons", stats.comparisons());
+
+        let mut data = vec![5, 1, 4, 2, 3];
+        let trace = SortTrace::new(&data);
+        select_nth_unstable_observed(data.as_mut_slice(), 2, &trace);
+        assert_eq!(Some(&data), trace.states().last());
+        assert_eq!(trace.stats().swaps() + trace.stats().writes() + 1, trace.states().len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_select_out_of_bounds() {
+        select_nth_unstable(&mut [1, 2, 3], 3);
+    }
+
+    #[test]
+    fn test_partial_sort() {
+        for &k in [0, 1, 10, 999, 1_000, 2_000].iter() {
+            let data = random_data(1_000, 100);
+            let mut expected = data.clone();
+            expected.sort();
+
+            let mut sorted = data.clone();
+            partial_sort(sorted.as_mut_slice(), k);
+            let k = k.min(data.len());
+            assert_eq!(expected[..k], sorted[..k]);
+
+            sorted[k..].sort();
+            assert_eq!(expected, sorted);
+        }
+
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        partial_sort_by_key(data.as_mut_slice(), 2, |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2)], data[..2]);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(None, median::<u32>(&mut []));
+        assert_eq!(Some(&3), median(&mut [5, 3, 1]));
+        assert_eq!(Some(&2), median(&mut [4, 1, 3, 2]));
+
+        let mut data = random_data(10_001, u32::MAX);
+        let mut expected = data.clone();
+        expected.sort();
+        assert_eq!(Some(&expected[5_000]), median(data.as_mut_slice()));
+    }
+
+    #[bench]
+    fn median_random(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| *median(data.clone().as_mut_slice()).unwrap())
+    }
+
+    #[bench]
+    fn median_by_sorting(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| {
+            let mut data = data.clone();
+            intro_sort(data.as_mut_slice());
+            data[(data.len() - 1) / 2]
+        })
+    }
+
+    #[bench]
+    fn partial_sort_top_100(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| partial_sort(data.clone().as_mut_slice(), 100))
+    }
+}
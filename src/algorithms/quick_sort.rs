@@ -1,3 +1,6 @@
+use std::cmp::Ordering;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
 /// Efficient sorting against large sets.
 ///
 /// This divide-and-conquer sorting algorithm performs `O(n log n)` in average and best case scenarios,
@@ -5,58 +8,101 @@
 ///
 /// Quicksort is often faster in practice than other `O(n log n)` algorithms due to sequential and
 /// localized memory references that work well with modern CPU caches.
+///
+/// This is the textbook algorithm. It degrades to `O(n^2)` on data with many duplicates. Only the smaller side of
+/// every partition is sorted recursively, and the larger side in a loop, so the stack depth is `O(log n)` even then.
+/// Prefer `intro_sort` for real workloads.
 pub fn quick_sort<A: Ord>(data: &mut [A]) {
-    fn qsort<A: Ord>(data: &mut [A]) {
-        match data.len() {
-            0 | 1 => (),
-            _ => {
-                let pivot = find_pivot(data);
-                let pivot = partition(data, pivot);
-
-                qsort(&mut data[..pivot]);
-                qsort(&mut data[pivot + 1..]);
+    quick_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with quicksort, using the comparator function to order the elements.
+pub fn quick_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    quick_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with quicksort, ordering the elements by the keys the function extracts from them.
+pub fn quick_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    quick_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with quicksort, reporting the comparisons and swaps to the observer.
+pub fn quick_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    quick_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with quicksort, using the comparator function to order the elements, and reporting the swaps to
+/// the observer.
+pub fn quick_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
+    fn qsort<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(mut data: &mut [A], compare: &mut F, observer: &O) {
+        while data.len() > 1 {
+            let pivot = find_pivot(data, compare);
+            let pivot = partition(data, pivot, compare, observer);
+
+            let (left, right) = { data }.split_at_mut(pivot);
+            let right = &mut right[1..];
+
+            if left.len() < right.len() {
+                qsort(left, compare, observer);
+                data = right;
+            } else {
+                qsort(right, compare, observer);
+                data = left;
             }
         }
     }
 
-    /// Partitioning makes the left values of the pivot to be less, and the right values to be greater.
-    fn partition<A: Ord>(data: &mut [A], pivot: usize) -> usize {
-        let (mut next_position, mut index) = (0, 0);
-        let right_index = data.len() - 1;
+    qsort(data, &mut compare, observer)
+}
 
-        data.swap(pivot, right_index);
+/// Partitioning makes the left values of the pivot to be less, and the right values to be greater.
+///
+/// Returns the new index of the pivot, which starts at the given index. The swaps are reported to the observer.
+pub fn partition<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], pivot: usize, compare: &mut F, observer: &O) -> usize {
+    let (mut next_position, mut index) = (0, 0);
+    let right_index = data.len() - 1;
 
-        while index < right_index {
-            if data[index] <= data[right_index] {
-                data.swap(index, next_position);
-                next_position += 1;
-            }
+    data.swap(pivot, right_index);
+    observer.swapped(data, pivot, right_index);
 
-            index += 1;
+    while index < right_index {
+        if compare(&data[index], &data[right_index]) != Ordering::Greater {
+            data.swap(index, next_position);
+            observer.swapped(data, index, next_position);
+            next_position += 1;
         }
 
-        data.swap(next_position, right_index);
-        next_position
+        index += 1;
     }
 
-    /// Finds the median of left, middle and right.
-    fn find_pivot<A: Ord>(data: &[A]) -> usize {
-        let (left, right) = (0, data.len() - 1);
-        let middle = left + (right - left) / 2; // Avoid integer overflow vs (left + right) / 2.
+    data.swap(next_position, right_index);
+    observer.swapped(data, next_position, right_index);
+    next_position
+}
 
-        match (&data[left], &data[middle], &data[right]) {
-            (l, m, r) if l <= m && m <= r => middle,
-            (l, m, r) if l >= m && l <= r => left,
-            _ => right,
-        }
-    }
+/// Finds the median of left, middle and right.
+pub fn find_pivot<A, F: FnMut(&A, &A) -> Ordering>(data: &[A], compare: &mut F) -> usize {
+    let (left, right) = (0, data.len() - 1);
+    let middle = left + (right - left) / 2; // Avoid integer overflow vs (left + right) / 2.
 
-    qsort(data)
+    let (l, m, r) = (&data[left], &data[middle], &data[right]);
+    if compare(l, m) != Ordering::Greater && compare(m, r) != Ordering::Greater {
+        middle
+    } else if compare(l, m) != Ordering::Less && compare(l, r) != Ordering::Greater {
+        left
+    } else {
+        right
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::thread;
+    use algorithms::sort_stats::{SortStats, SortTrace};
 
     #[test]
     fn test_quick_sort() {
@@ -64,4 +110,58 @@ mod tests {
         quick_sort(data.as_mut_slice());
         assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
     }
+
+    /// All-equal data makes every partition as unbalanced as possible, which must not take a stack frame per
+    /// element.
+    #[test]
+    fn test_quick_sort_stack_depth() {
+        let sorted = thread::Builder::new().stack_size(64 * 1024).spawn(|| {
+            let mut data = vec![7u32; 5_000];
+            quick_sort(data.as_mut_slice());
+            data.iter().all(|&element| element == 7)
+        }).unwrap().join().unwrap();
+
+        assert!(sorted);
+    }
+
+    #[test]
+    fn test_quick_sort_by() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        quick_sort_by(data.as_mut_slice(), |a, b| b.cmp(a));
+        assert_eq!([16, 9, 8, 5, 4, 3, 2, 1, 0], data.as_slice());
+
+        let mut data = vec![2.5, -1.0, 0.5, 10.0];
+        quick_sort_by(data.as_mut_slice(), |a: &f64, b| a.partial_cmp(b).unwrap());
+        assert_eq!([-1.0, 0.5, 2.5, 10.0], data.as_slice());
+    }
+
+    #[test]
+    fn test_quick_sort_by_key() {
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        quick_sort_by_key(data.as_mut_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("a", 3), ("d", 4)], data.as_slice());
+    }
+
+    /// The median-of-three pivot makes sorted data cheap. Lomuto partitioning swaps every element that is not
+    /// greater than the pivot, though, so data whose elements are all equal takes `O(n^2)` swaps as well as
+    /// comparisons.
+    #[test]
+    fn test_quick_sort_observed() {
+        let mut data: Vec<u32> = (0 .. 100).collect();
+        let stats = SortStats::new();
+        quick_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!((0 .. 100).collect::<Vec<u32>>(), data);
+        assert!(stats.comparisons() < 1_000, "{} comparisons", stats.comparisons());
+
+        let mut data = vec![7u32; 100];
+        let stats = SortStats::new();
+        quick_sort_observed(data.as_mut_slice(), &stats);
+        assert!(stats.swaps() > 100 * 99 / 2, "{} swaps", stats.swaps());
+
+        let mut data = vec![4, 1, 3, 5, 2];
+        let trace = SortTrace::new(&data);
+        quick_sort_observed(data.as_mut_slice(), &trace);
+        assert_eq!(Some(&vec![1, 2, 3, 4, 5]), trace.states().last());
+        assert_eq!(trace.stats().swaps() + 1, trace.states().len());
+    }
 }
@@ -0,0 +1,435 @@
+//! Non-comparison sorts, which look at the bytes of the keys rather than comparing them.
+//!
+//! They beat comparison sorts, which need `O(n log n)` comparisons, when the keys are short compared to the number
+//! of elements, e.g. when sorting millions of 32-bit or 64-bit integers.
+use std::mem;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// Sub-slices up to this length are sorted with insertion sort in `american_flag_sort`.
+static INSERTION_SORT_THRESHOLD: usize = 32usize;
+
+/// `counting_sort` refuses ranges of keys larger than this, which would need more than 128 MiB of counters.
+static MAX_COUNTING_RANGE: u64 = 1u64 << 24;
+
+/// A key that can be sorted by its bytes.
+pub trait RadixKey: Copy {
+    /// The number of bytes of the key.
+    const BYTES: usize;
+
+    /// Returns the key as an unsigned integer of `BYTES` bytes, which orders like the key itself.
+    fn to_radix(self) -> u64;
+}
+
+macro_rules! radix_key_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl RadixKey for $t {
+                const BYTES: usize = mem::size_of::<$t>();
+
+                #[inline]
+                fn to_radix(self) -> u64 {
+                    self as u64
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! radix_key_signed {
+    ($($t:ty, $u:ty, $min:expr);*) => {
+        $(
+            impl RadixKey for $t {
+                const BYTES: usize = mem::size_of::<$t>();
+
+                /// Flipping the sign bit orders the negative numbers before the positive ones.
+                #[inline]
+                fn to_radix(self) -> u64 {
+                    (self as $u ^ $min as $u) as u64
+                }
+            }
+        )*
+    }
+}
+
+radix_key_unsigned!(u8, u16, u32, u64, usize);
+radix_key_signed!(i8, u8, i8::MIN; i16, u16, i16::MIN; i32, u32, i32::MIN; i64, u64, i64::MIN; isize, usize, isize::MIN);
+
+impl RadixKey for f32 {
+    const BYTES: usize = 4;
+
+    /// Positive floats order like their bits once the sign bit is set. Negative floats order in reverse, so all
+    /// their bits are flipped. This orders `-NaN < -inf < ... < -0.0 < 0.0 < ... < inf < NaN`.
+    #[inline]
+    fn to_radix(self) -> u64 {
+        let bits = self.to_bits();
+        (if bits >> 31 == 1 { !bits } else { bits | 1 << 31 }) as u64
+    }
+}
+
+impl RadixKey for f64 {
+    const BYTES: usize = 8;
+
+    /// Orders like the `f32` key.
+    #[inline]
+    fn to_radix(self) -> u64 {
+        let bits = self.to_bits();
+        if bits >> 63 == 1 { !bits } else { bits | 1 << 63 }
+    }
+}
+
+/// Sorts the data with a least significant digit (LSD) radix sort.
+///
+/// Every pass distributes the elements by one byte of their keys, starting with the least significant byte. Every
+/// pass is stable, so after the last pass the elements are sorted by all the bytes. The histograms of all the bytes
+/// are counted in a single pass over the data up front, and the passes over bytes that are equal for all elements
+/// are skipped.
+///
+/// It performs `O(w n)` for keys of `w` bytes, and needs a buffer of `n` elements.
+pub fn radix_sort<A: RadixKey>(data: &mut [A]) {
+    radix_sort_observed(data, &NoObserver)
+}
+
+/// Sorts the data with an LSD radix sort, reporting the writes to the data and the buffer to the observer.
+pub fn radix_sort_observed<A: RadixKey, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    let bytes = A::BYTES;
+    if data.len() < 2 {
+        return;
+    }
+
+    let mut counts = vec![[0usize; 256]; bytes];
+    for element in data.iter() {
+        let key = element.to_radix();
+        for (byte, count) in counts.iter_mut().enumerate() {
+            count[((key >> (byte * 8)) & 0xff) as usize] += 1;
+        }
+    }
+
+    let mut buffer: Vec<A> = data.to_vec();
+    let mut sorted_in_buffer = false;
+
+    for (byte, count) in counts.iter().enumerate() {
+        if count.contains(&data.len()) {
+            continue;
+        }
+
+        let mut offsets = [0usize; 256];
+        for digit in 1 .. 256 {
+            offsets[digit] = offsets[digit - 1] + count[digit - 1];
+        }
+
+        let (from, to): (&[A], &mut [A]) = if sorted_in_buffer { (&buffer, data) } else { (data, &mut buffer) };
+        for &element in from.iter() {
+            let digit = ((element.to_radix() >> (byte * 8)) & 0xff) as usize;
+            to[offsets[digit]] = element;
+            observer.wrote(to, offsets[digit] .. offsets[digit] + 1);
+            offsets[digit] += 1;
+        }
+
+        sorted_in_buffer = !sorted_in_buffer;
+    }
+
+    if sorted_in_buffer {
+        data.copy_from_slice(&buffer);
+        observer.wrote(data, 0 .. data.len());
+    }
+}
+
+/// Sorts the data with a counting sort.
+///
+/// The occurrences of every key between the smallest and the largest are counted, and the elements are then moved
+/// to the positions the counts give them. It is stable, and performs `O(n + k)` for a range of `k` keys, so it is
+/// only suitable for small ranges, such as ages or grades.
+///
+/// Panics if the range of the keys is larger than `2^24`.
+pub fn counting_sort<A: RadixKey>(data: &mut [A]) {
+    counting_sort_observed(data, &NoObserver)
+}
+
+/// Sorts the data with a counting sort, reporting the writes to the observer.
+pub fn counting_sort_observed<A: RadixKey, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    if data.len() < 2 {
+        return;
+    }
+
+    let (min, max) = data.iter().fold((u64::MAX, 0u64), |(min, max), element| {
+        let key = element.to_radix();
+        (min.min(key), max.max(key))
+    });
+
+    let range = max - min;
+    if range >= MAX_COUNTING_RANGE {
+        panic!("the range of the keys {} has to be less than {}", range, MAX_COUNTING_RANGE);
+    }
+
+    let mut offsets = vec![0usize; range as usize + 1];
+    for element in data.iter() {
+        offsets[(element.to_radix() - min) as usize] += 1;
+    }
+
+    let mut total = 0;
+    for offset in offsets.iter_mut() {
+        let count = *offset;
+        *offset = total;
+        total += count;
+    }
+
+    let buffer: Vec<A> = data.to_vec();
+    for &element in buffer.iter() {
+        let key = (element.to_radix() - min) as usize;
+        data[offsets[key]] = element;
+        observer.wrote(data, offsets[key] .. offsets[key] + 1);
+        offsets[key] += 1;
+    }
+}
+
+/// Returns the byte of the key at the given depth plus one, or zero if the key is shorter, so that shorter keys
+/// sort before their extensions.
+#[inline]
+fn digit(key: &[u8], depth: usize) -> usize {
+    if depth < key.len() { key[depth] as usize + 1 } else { 0 }
+}
+
+/// Sorts byte string keys with American flag sort (McIlroy et al., 1993), an in-place most significant digit (MSD)
+/// radix sort.
+///
+/// The keys are distributed into 257 buckets by their first byte, one for every byte and one for the keys that
+/// have ended. The buckets are formed in place, by swapping every key into the next free position of its bucket.
+/// Every bucket is then sorted in the same way by the next byte, except the one of ended keys, whose keys are all
+/// equal. Short buckets are sorted with insertion sort instead.
+///
+/// It only looks at the bytes needed to tell the keys apart, and performs `O(n w)` in the worst case for keys of
+/// `w` bytes. It is not stable. The buckets waiting to be sorted are kept on an explicit stack, so long keys cannot
+/// overflow the call stack.
+pub fn american_flag_sort<A: AsRef<[u8]>>(data: &mut [A]) {
+    american_flag_sort_observed(data, &NoObserver)
+}
+
+/// Sorts byte string keys with American flag sort, reporting the swaps to the observer, and the comparisons of the
+/// insertion sort of short buckets.
+pub fn american_flag_sort_observed<A: AsRef<[u8]>, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    let mut stack = vec![(0, data.len(), 0)];
+
+    while let Some((start, end, depth)) = stack.pop() {
+        let data = &mut data[start..end];
+
+        if data.len() <= INSERTION_SORT_THRESHOLD {
+            for i in 1 .. data.len() {
+                let mut x = i;
+                while x > 0 && {
+                    observer.compared();
+                    data[x - 1].as_ref()[depth..] > data[x].as_ref()[depth..]
+                } {
+                    data.swap(x, x - 1);
+                    observer.swapped(data, x, x - 1);
+                    x -= 1;
+                }
+            }
+            continue;
+        }
+
+        let mut counts = [0usize; 257];
+        for key in data.iter() {
+            counts[digit(key.as_ref(), depth)] += 1;
+        }
+
+        let mut offsets = [0usize; 257];
+        for bucket in 1 .. 257 {
+            offsets[bucket] = offsets[bucket - 1] + counts[bucket - 1];
+        }
+
+        // Every bucket fills up from its offset. The key at the next free position of a bucket is swapped to the
+        // next free position of its own bucket, until a key that belongs to the bucket arrives.
+        let mut next = offsets;
+        for bucket in 0 .. 257 {
+            let bucket_end = offsets[bucket] + counts[bucket];
+            while next[bucket] < bucket_end {
+                let target = digit(data[next[bucket]].as_ref(), depth);
+                if target == bucket {
+                    next[bucket] += 1;
+                } else {
+                    data.swap(next[bucket], next[target]);
+                    observer.swapped(data, next[bucket], next[target]);
+                    next[target] += 1;
+                }
+            }
+        }
+
+        for bucket in 1 .. 257 {
+            if counts[bucket] > 1 {
+                stack.push((start + offsets[bucket], start + offsets[bucket] + counts[bucket], depth + 1));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{f32, f64};
+    use test::Bencher;
+    use rand::Rng;
+    use test_helpers::rng;
+    use algorithms::sort_stats::{SortStats, SortTrace};
+
+    static BENCH_SIZE: usize = 100_000usize;
+
+    #[test]
+    fn test_radix_sort_unsigned() {
+        let mut data = vec![9u32, 8, 1, 5, 3, 16, 2, 0, 4];
+        radix_sort(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
+
+        let mut rng = rng();
+        for &size in [0, 1, 2, 100, 10_000].iter() {
+            let mut data: Vec<u64> = (0 .. size).map(|_| rng.gen()).collect();
+            data.push(u64::MAX);
+            data.push(0);
+            let mut expected = data.clone();
+            expected.sort();
+            radix_sort(data.as_mut_slice());
+            assert_eq!(expected, data);
+
+            // Only the lowest byte differs, so all the other passes are skipped.
+            let mut data: Vec<u16> = (0 .. size).map(|_| rng.gen::<u8>() as u16 | 0x1200).collect();
+            let mut expected = data.clone();
+            expected.sort();
+            radix_sort(data.as_mut_slice());
+            assert_eq!(expected, data);
+        }
+    }
+
+    #[test]
+    fn test_radix_sort_signed() {
+        let mut rng = rng();
+        let mut data: Vec<i32> = (0 .. 10_000).map(|_| rng.gen()).collect();
+        data.extend_from_slice(&[i32::MIN, i32::MAX, -1, 0, 1]);
+        let mut expected = data.clone();
+        expected.sort();
+        radix_sort(data.as_mut_slice());
+        assert_eq!(expected, data);
+
+        let mut data: Vec<i8> = vec![5, -3, i8::MIN, 0, i8::MAX, -1];
+        radix_sort(data.as_mut_slice());
+        assert_eq!([i8::MIN, -3, -1, 0, 5, i8::MAX], data.as_slice());
+
+        let mut data: Vec<i64> = vec![5, -3, i64::MIN, 0, i64::MAX, -1];
+        radix_sort(data.as_mut_slice());
+        assert_eq!([i64::MIN, -3, -1, 0, 5, i64::MAX], data.as_slice());
+    }
+
+    #[test]
+    fn test_radix_sort_floats() {
+        let mut rng = rng();
+        let mut data: Vec<f64> = (0 .. 10_000).map(|_| (rng.gen::<f64>() - 0.5) * 1e6).collect();
+        data.extend_from_slice(&[f64::INFINITY, f64::NEG_INFINITY, f64::MIN_POSITIVE, -0.0, 0.0, f64::MAX, f64::MIN]);
+        let mut expected = data.clone();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        radix_sort(data.as_mut_slice());
+        assert_eq!(expected, data);
+
+        let mut data = vec![1.5f32, -0.0, f32::NAN, -2.5, 0.0, f32::NEG_INFINITY];
+        radix_sort(data.as_mut_slice());
+        assert_eq!(f32::NEG_INFINITY, data[0]);
+        assert_eq!(-2.5, data[1]);
+        assert!(data[2] == 0.0 && data[2].is_sign_negative());
+        assert!(data[3] == 0.0 && data[3].is_sign_positive());
+        assert_eq!(1.5, data[4]);
+        assert!(data[5].is_nan());
+    }
+
+    #[test]
+    fn test_counting_sort() {
+        let mut data = vec![9u8, 8, 1, 5, 3, 16, 2, 0, 4, 8];
+        counting_sort(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 8, 9, 16], data.as_slice());
+
+        let mut rng = rng();
+        let mut data: Vec<i16> = (0 .. 10_000).map(|_| rng.gen_range(-100, 100)).collect();
+        let mut expected = data.clone();
+        expected.sort();
+        counting_sort(data.as_mut_slice());
+        assert_eq!(expected, data);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_counting_sort_large_range() {
+        counting_sort(&mut [0u32, 1 << 30]);
+    }
+
+    #[test]
+    fn test_american_flag_sort() {
+        let mut data = vec!["banana", "apple", "", "cherry", "app", "apple", "b", "ba", "application"];
+        american_flag_sort(data.as_mut_slice());
+        assert_eq!(["", "app", "apple", "apple", "application", "b", "ba", "banana", "cherry"], data.as_slice());
+
+        // Keys with long common prefixes and every byte value.
+        let mut rng = rng();
+        let mut data: Vec<Vec<u8>> = (0 .. 10_000).map(|_| {
+            let mut key = vec![0xff; rng.gen_range(0, 3) * 100];
+            let length = rng.gen_range(0, 4);
+            key.extend((0 .. length).map(|_| rng.gen::<u8>()));
+            key
+        }).collect();
+        let mut expected = data.clone();
+        expected.sort();
+        american_flag_sort(data.as_mut_slice());
+        assert_eq!(expected, data);
+    }
+
+    /// Every pass writes every element once, and the passes over equal bytes are skipped.
+    #[test]
+    fn test_radix_sort_observed() {
+        let mut rng = rng();
+        let mut data: Vec<u32> = (0 .. 1_000).map(|_| rng.gen()).collect();
+        let stats = SortStats::new();
+        radix_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!(4 * 1_000, stats.writes());
+        assert_eq!(0, stats.comparisons());
+
+        let mut data: Vec<u32> = (0 .. 1_000).map(|_| rng.gen_range(0, 256)).collect();
+        let stats = SortStats::new();
+        radix_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!(2 * 1_000, stats.writes());
+
+        let mut data = vec![3u8, 1, 2, 1];
+        let trace = SortTrace::new(&data);
+        counting_sort_observed(data.as_mut_slice(), &trace);
+        let states = vec![vec![3, 1, 2, 1], vec![3, 1, 2, 3], vec![1, 1, 2, 3], vec![1, 1, 2, 3], vec![1, 1, 2, 3]];
+        assert_eq!(states, trace.states());
+
+        let mut data = vec!["b", "a", "ab"];
+        let stats = SortStats::new();
+        american_flag_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!(["a", "ab", "b"], data.as_slice());
+        assert_eq!((3, 2), (stats.comparisons(), stats.swaps()));
+    }
+
+    #[bench]
+    fn radix_sort_u32(b: &mut Bencher) {
+        let mut rng = rng();
+        let data: Vec<u32> = (0 .. BENCH_SIZE).map(|_| rng.gen()).collect();
+        b.iter(|| radix_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn std_sort_unstable_u32(b: &mut Bencher) {
+        let mut rng = rng();
+        let data: Vec<u32> = (0 .. BENCH_SIZE).map(|_| rng.gen()).collect();
+        b.iter(|| data.clone().sort_unstable())
+    }
+
+    #[bench]
+    fn american_flag_sort_strings(b: &mut Bencher) {
+        let mut rng = rng();
+        let data: Vec<String> = (0 .. BENCH_SIZE).map(|_| format!("user-{}", rng.gen::<u32>())).collect();
+        b.iter(|| american_flag_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn std_sort_unstable_strings(b: &mut Bencher) {
+        let mut rng = rng();
+        let data: Vec<String> = (0 .. BENCH_SIZE).map(|_| format!("user-{}", rng.gen::<u32>())).collect();
+        b.iter(|| data.clone().sort_unstable())
+    }
+}
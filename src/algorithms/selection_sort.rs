@@ -1,28 +1,60 @@
+use std::cmp::Ordering;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
 /// Efficient sorting against small sets.
 ///
 /// Selection sort is inefficient against large sets. It requires no additional memory.
 ///
 /// The write performance of `O(n)` is better than that of e.g. insertion sort's `O(n^2)`.
 pub fn selection_sort<A: Ord>(data: &mut [A]) {
+    selection_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with selection sort, using the comparator function to order the elements.
+pub fn selection_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    selection_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with selection sort, ordering the elements by the keys the function extracts from them.
+pub fn selection_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    selection_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with selection sort, reporting the comparisons and swaps to the observer.
+pub fn selection_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    selection_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with selection sort, using the comparator function to order the elements, and reporting the
+/// swaps to the observer. Elements that are already in place are not swapped.
+pub fn selection_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
     let (mut i, size) = (0, data.len());
 
     while i < size {
         let (mut x, mut current_min) = (i + 1, i);
         while x < size {
-            if data[x] < data[current_min] {
+            if compare(&data[x], &data[current_min]) == Ordering::Less {
                 current_min = x;
             }
             x += 1;
         }
-        data.swap(i, current_min);
+        if current_min != i {
+            data.swap(i, current_min);
+            observer.swapped(data, i, current_min);
+        }
         i += 1;
     }
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::Rng;
+    use test_helpers::rng;
+    use algorithms::sort_stats::{SortStats, SortTrace};
 
     #[test]
     fn test_selection_sort() {
@@ -30,4 +62,56 @@ mod tests {
         selection_sort(data.as_mut_slice());
         assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
     }
+
+    #[test]
+    fn test_selection_sort_by() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        selection_sort_by(data.as_mut_slice(), |a, b| b.cmp(a));
+        assert_eq!([16, 9, 8, 5, 4, 3, 2, 1, 0], data.as_slice());
+
+        let mut data = vec![2.5, -1.0, 0.5, 10.0];
+        selection_sort_by(data.as_mut_slice(), |a: &f64, b| a.partial_cmp(b).unwrap());
+        assert_eq!([-1.0, 0.5, 2.5, 10.0], data.as_slice());
+    }
+
+    #[test]
+    fn test_selection_sort_by_key() {
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        selection_sort_by_key(data.as_mut_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("a", 3), ("d", 4)], data.as_slice());
+    }
+
+    /// As the documentation claims, selection sort swaps every element into place at most once, so it performs at
+    /// most `n - 1` swaps and `n (n - 1) / 2` comparisons, regardless of the order of the data.
+    #[test]
+    fn test_selection_sort_observed() {
+        let mut rng = rng();
+        for &size in [0, 1, 10, 100, 1_000].iter() {
+            let mut data: Vec<u32> = (0 .. size).map(|_| rng.gen_range(0, 100)).collect();
+            let stats = SortStats::new();
+            selection_sort_observed(data.as_mut_slice(), &stats);
+
+            assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(stats.swaps() <= (size as usize).saturating_sub(1));
+            assert_eq!(size as usize * (size as usize).saturating_sub(1) / 2, stats.comparisons());
+            assert_eq!(0, stats.writes());
+        }
+
+        // Sorted data is not swapped at all, and reversed data takes a swap for each pair from the ends.
+        let mut data: Vec<u32> = (0 .. 10).collect();
+        let stats = SortStats::new();
+        selection_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!(0, stats.swaps());
+
+        let mut data: Vec<u32> = (0 .. 10).rev().collect();
+        let stats = SortStats::new();
+        selection_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!(5, stats.swaps());
+
+        let mut data = vec![2, 3, 1];
+        let trace = SortTrace::new(&data);
+        selection_sort_observed(data.as_mut_slice(), &trace);
+        assert_eq!(vec![vec![2, 3, 1], vec![1, 3, 2], vec![1, 2, 3]], trace.states());
+        assert_eq!(2, trace.stats().swaps());
+    }
 }
@@ -0,0 +1,227 @@
+use std::cmp::Ordering;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// The gaps of Ciura's sequence that were found empirically. Larger gaps are extended by a factor of 2.25.
+static CIURA_GAPS: [usize; 8] = [1, 4, 10, 23, 57, 132, 301, 701];
+
+/// The sequence of gaps that shell sort sorts the data with, from the largest to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GapSequence {
+    /// `n / 2, n / 4, ..., 1` (Shell, 1959), which performs `O(n^2)` in the worst case.
+    Shell,
+    /// `1, 4, 13, 40, ...`, that is `(3^k - 1) / 2` up to `n / 3` (Knuth, 1973), which performs `O(n^(3/2))` in the
+    /// worst case.
+    Knuth,
+    /// `1, 4, 10, 23, 57, 132, 301, 701, ...` (Ciura, 2001), which is the fastest known in practice.
+    Ciura,
+    /// `1, 8, 23, 77, 281, ...`, that is `4^k + 3 * 2^(k - 1) + 1` (Sedgewick, 1986), which performs `O(n^(4/3))` in
+    /// the worst case.
+    Sedgewick,
+}
+
+impl GapSequence {
+    /// Returns the gaps for data of the given length, from the largest to 1.
+    pub fn gaps(&self, length: usize) -> Vec<usize> {
+        let mut gaps = vec![1];
+
+        match *self {
+            GapSequence::Shell => {
+                gaps = Vec::new();
+                let mut gap = length / 2;
+                while gap > 0 {
+                    gaps.push(gap);
+                    gap /= 2;
+                }
+                gaps.reverse();
+            },
+            GapSequence::Knuth => {
+                let mut gap = 4;
+                while gap <= length / 3 {
+                    gaps.push(gap);
+                    gap = 3 * gap + 1;
+                }
+            },
+            GapSequence::Ciura => {
+                let mut gap = 1;
+                for &next in CIURA_GAPS[1..].iter() {
+                    if next >= length {
+                        break;
+                    }
+                    gaps.push(next);
+                    gap = next;
+                }
+                if gap == CIURA_GAPS[CIURA_GAPS.len() - 1] {
+                    loop {
+                        gap = gap * 9 / 4;
+                        if gap >= length {
+                            break;
+                        }
+                        gaps.push(gap);
+                    }
+                }
+            },
+            GapSequence::Sedgewick => {
+                let mut k = 1;
+                loop {
+                    let gap = (1 << (2 * k)) + 3 * (1 << (k - 1)) + 1;
+                    if gap >= length {
+                        break;
+                    }
+                    gaps.push(gap);
+                    k += 1;
+                }
+            },
+        }
+
+        gaps.reverse();
+        gaps
+    }
+}
+
+/// Efficient sorting against medium-sized sets, without any additional memory.
+///
+/// Shell sort (Shell, 1959) is an insertion sort that first sorts the elements that are far apart, the ones at
+/// every `gap` positions, for a decreasing sequence of gaps. By the final insertion sort with a gap of 1, every
+/// element is close to its sorted position. The performance depends on the gap sequence; this uses Ciura's gaps.
+///
+/// Shell sort is not stable.
+pub fn shell_sort<A: Ord>(data: &mut [A]) {
+    shell_sort_with_gaps(data, GapSequence::Ciura)
+}
+
+/// Sorts the data with shell sort, using the given gap sequence.
+pub fn shell_sort_with_gaps<A: Ord>(data: &mut [A], gaps: GapSequence) {
+    shell_sort_observed_by(data, gaps, |a, b| a.cmp(b), &NoObserver)
+}
+
+/// Sorts the data with shell sort, using the comparator function to order the elements.
+pub fn shell_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    shell_sort_observed_by(data, GapSequence::Ciura, compare, &NoObserver)
+}
+
+/// Sorts the data with shell sort, ordering the elements by the keys the function extracts from them.
+pub fn shell_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    shell_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with shell sort, using the given gap sequence, and reporting the comparisons and swaps to the
+/// observer.
+pub fn shell_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], gaps: GapSequence, observer: &O) {
+    shell_sort_observed_by(data, gaps, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with shell sort, using the given gap sequence and the comparator function to order the elements,
+/// and reporting the swaps to the observer.
+pub fn shell_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(
+    data: &mut [A],
+    gaps: GapSequence,
+    mut compare: F,
+    observer: &O
+) {
+    for gap in gaps.gaps(data.len()) {
+        for i in gap .. data.len() {
+            let mut x = i;
+            while x >= gap && compare(&data[x - gap], &data[x]) == Ordering::Greater {
+                data.swap(x, x - gap);
+                observer.swapped(data, x, x - gap);
+                x -= gap;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use test_helpers::random_data;
+    use algorithms::sort_stats::SortStats;
+
+    static BENCH_SIZE: usize = 10_000usize;
+
+    static SEQUENCES: [GapSequence; 4] = [GapSequence::Shell, GapSequence::Knuth, GapSequence::Ciura, GapSequence::Sedgewick];
+
+    #[test]
+    fn test_gaps() {
+        assert_eq!(vec![50, 25, 12, 6, 3, 1], GapSequence::Shell.gaps(100));
+        assert_eq!(vec![13, 4, 1], GapSequence::Knuth.gaps(100));
+        assert_eq!(vec![57, 23, 10, 4, 1], GapSequence::Ciura.gaps(100));
+        assert_eq!(vec![77, 23, 8, 1], GapSequence::Sedgewick.gaps(100));
+        assert_eq!(vec![1577, 701, 301, 132, 57, 23, 10, 4, 1], GapSequence::Ciura.gaps(2_000));
+
+        for sequence in SEQUENCES.iter() {
+            assert_eq!(vec![1], sequence.gaps(2));
+        }
+    }
+
+    #[test]
+    fn test_shell_sort() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        shell_sort(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
+
+        for &sequence in SEQUENCES.iter() {
+            for &size in [0, 1, 2, 100, 1_000].iter() {
+                for &max in [3, u32::MAX].iter() {
+                    let mut data = random_data(size, max);
+                    let mut expected = data.clone();
+                    expected.sort();
+
+                    shell_sort_with_gaps(data.as_mut_slice(), sequence);
+                    assert_eq!(expected, data);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shell_sort_by() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        shell_sort_by(data.as_mut_slice(), |a, b| b.cmp(a));
+        assert_eq!([16, 9, 8, 5, 4, 3, 2, 1, 0], data.as_slice());
+
+        let mut data = vec![("b", 2), ("a", 3), ("c", 1), ("d", 4)];
+        shell_sort_by_key(data.as_mut_slice(), |&(_, n)| n);
+        assert_eq!([("c", 1), ("b", 2), ("a", 3), ("d", 4)], data.as_slice());
+    }
+
+    /// Reversed data is the worst case of insertion sort, with `n (n - 1) / 2` swaps, but not of shell sort.
+    #[test]
+    fn test_shell_sort_observed() {
+        for &sequence in SEQUENCES.iter() {
+            let mut data: Vec<u32> = (0 .. 1_000).rev().collect();
+            let stats = SortStats::new();
+            shell_sort_observed(data.as_mut_slice(), sequence, &stats);
+
+            assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(stats.swaps() < 1_000 * 999 / 20, "{:?}: {} swaps", sequence, stats.swaps());
+        }
+    }
+
+    #[bench]
+    fn shell_sort_shell_gaps(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| shell_sort_with_gaps(data.clone().as_mut_slice(), GapSequence::Shell))
+    }
+
+    #[bench]
+    fn shell_sort_knuth_gaps(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| shell_sort_with_gaps(data.clone().as_mut_slice(), GapSequence::Knuth))
+    }
+
+    #[bench]
+    fn shell_sort_ciura_gaps(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| shell_sort_with_gaps(data.clone().as_mut_slice(), GapSequence::Ciura))
+    }
+
+    #[bench]
+    fn shell_sort_sedgewick_gaps(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| shell_sort_with_gaps(data.clone().as_mut_slice(), GapSequence::Sedgewick))
+    }
+}
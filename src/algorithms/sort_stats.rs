@@ -0,0 +1,194 @@
+//! Instrumentation of the sorts, for comparing the algorithms by the operations they perform rather than by time.
+//!
+//! Every in-place sort has an `_observed` variant, which reports the comparisons, swaps and writes it performs to a
+//! `SortObserver`. `SortStats` counts them, and `SortTrace` also records the state of the data after every swap and
+//! write. The `_observed_by` variants take a comparator, which has to call `SortObserver::compared` itself if the
+//! comparisons should be counted.
+//!
+//! `merge_sort` returns a new vector, so its writes go to vectors outside the traced data and are only counted. The
+//! selections of `quick_select` are instrumented too. The parallel sorts are not, because the observers keep their
+//! counts in `Cell`s and cannot be shared between threads, and neither is the external sort, which works on records
+//! in files rather than on a slice.
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::ops::Range;
+
+/// Receives the operations a sort performs.
+///
+/// The data passed to the observer is the slice that the sort is working on, which is usually a part of the data
+/// that is being sorted, and is sometimes a buffer.
+pub trait SortObserver<A> {
+    /// Called for every comparison of two elements.
+    fn compared(&self) {}
+
+    /// Called after the elements at the indices `a` and `b` of the data were swapped.
+    fn swapped(&self, _data: &[A], _a: usize, _b: usize) {}
+
+    /// Called after the elements in the range of the data were written, e.g. when they were moved back from a
+    /// buffer.
+    fn wrote(&self, _data: &[A], _range: Range<usize>) {}
+}
+
+/// An observer that ignores all operations, which the uninstrumented sorts use.
+pub struct NoObserver;
+
+impl<A> SortObserver<A> for NoObserver {}
+
+/// Counts the comparisons, swaps and writes of a sort.
+///
+/// A swap is not counted as two writes.
+#[derive(Debug, Default)]
+pub struct SortStats {
+    comparisons: Cell<usize>,
+    swaps: Cell<usize>,
+    writes: Cell<usize>,
+}
+
+impl SortStats {
+    /// Creates new counters at zero.
+    pub fn new() -> SortStats {
+        SortStats::default()
+    }
+
+    /// Returns the number of comparisons.
+    pub fn comparisons(&self) -> usize {
+        self.comparisons.get()
+    }
+
+    /// Returns the number of swaps.
+    pub fn swaps(&self) -> usize {
+        self.swaps.get()
+    }
+
+    /// Returns the number of elements written, other than by swaps.
+    pub fn writes(&self) -> usize {
+        self.writes.get()
+    }
+}
+
+impl<A> SortObserver<A> for SortStats {
+    fn compared(&self) {
+        self.comparisons.set(self.comparisons.get() + 1);
+    }
+
+    fn swapped(&self, _data: &[A], _a: usize, _b: usize) {
+        self.swaps.set(self.swaps.get() + 1);
+    }
+
+    fn wrote(&self, _data: &[A], range: Range<usize>) {
+        self.writes.set(self.writes.get() + range.len());
+    }
+}
+
+/// Counts the operations of a sort like `SortStats`, and records the state of the data after every swap and write.
+///
+/// The trace keeps its own copy of the data, which it updates from the slices the sort reports. Writes to buffers
+/// outside of the data are counted, but do not add a state.
+pub struct SortTrace<A> {
+    stats: SortStats,
+    start: usize,
+    state: RefCell<Vec<A>>,
+    states: RefCell<Vec<Vec<A>>>,
+}
+
+impl<A: Clone> SortTrace<A> {
+    /// Creates a new trace of the data, which has to be sorted in place with an `_observed` sort afterwards.
+    pub fn new(data: &[A]) -> SortTrace<A> {
+        SortTrace {
+            stats: SortStats::new(),
+            start: data.as_ptr() as usize,
+            state: RefCell::new(data.to_vec()),
+            states: RefCell::new(vec![data.to_vec()]),
+        }
+    }
+
+    /// Returns the counters of the operations.
+    pub fn stats(&self) -> &SortStats {
+        &self.stats
+    }
+
+    /// Returns the states of the data, starting with the state before the sort.
+    pub fn states(&self) -> Vec<Vec<A>> {
+        self.states.borrow().clone()
+    }
+
+    /// Returns the index in the traced data of the first element of the slice, or `None` if the slice is not a part
+    /// of the traced data.
+    fn offset(&self, data: &[A]) -> Option<usize> {
+        let (size, length) = (mem::size_of::<A>(), self.state.borrow().len());
+        let address = data.as_ptr() as usize;
+
+        if size == 0 || address < self.start || address + data.len() * size > self.start + length * size {
+            None
+        } else {
+            Some((address - self.start) / size)
+        }
+    }
+}
+
+impl<A: Clone> SortObserver<A> for SortTrace<A> {
+    fn compared(&self) {
+        SortObserver::<A>::compared(&self.stats);
+    }
+
+    fn swapped(&self, data: &[A], a: usize, b: usize) {
+        self.stats.swapped(data, a, b);
+
+        if let Some(offset) = self.offset(data) {
+            let mut state = self.state.borrow_mut();
+            state.swap(offset + a, offset + b);
+            self.states.borrow_mut().push(state.clone());
+        }
+    }
+
+    fn wrote(&self, data: &[A], range: Range<usize>) {
+        self.stats.wrote(data, range.clone());
+
+        if let Some(offset) = self.offset(data) {
+            let mut state = self.state.borrow_mut();
+            for index in range {
+                state[offset + index] = data[index].clone();
+            }
+            self.states.borrow_mut().push(state.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_stats() {
+        let stats = SortStats::new();
+        let data = [1, 2, 3];
+        SortObserver::<u32>::compared(&stats);
+        stats.swapped(&data, 0, 1);
+        stats.wrote(&data, 0..3);
+
+        assert_eq!(1, stats.comparisons());
+        assert_eq!(1, stats.swaps());
+        assert_eq!(3, stats.writes());
+    }
+
+    #[test]
+    fn test_sort_trace() {
+        let mut data = vec![3, 1, 2, 0];
+        let trace = SortTrace::new(&data);
+
+        data.swap(0, 1);
+        trace.swapped(&data, 0, 1);
+
+        // A part of the data is mapped to its position in the data.
+        data[2..].swap(0, 1);
+        trace.swapped(&data[2..], 0, 1);
+
+        // Writes to other memory are only counted.
+        let buffer = vec![5, 6];
+        trace.wrote(&buffer, 0..2);
+
+        assert_eq!(vec![vec![3, 1, 2, 0], vec![1, 3, 2, 0], vec![1, 3, 0, 2]], trace.states());
+        assert_eq!(2, trace.stats().swaps());
+        assert_eq!(2, trace.stats().writes());
+    }
+}
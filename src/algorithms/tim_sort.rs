@@ -0,0 +1,716 @@
+use std::cmp::{min, Ordering};
+use std::ptr;
+use std::slice;
+use algorithms::sort_stats::{NoObserver, SortObserver};
+
+/// Slices shorter than this are sorted with binary insertion sort alone.
+static MIN_MERGE: usize = 64usize;
+
+/// The initial number of consecutive wins of one run after which a merge switches to galloping.
+static MIN_GALLOP: usize = 7usize;
+
+/// A sorted run of the data, which is waiting on the stack to be merged.
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    start: usize,
+    length: usize,
+}
+
+/// Efficient sorting against real-world data, which often consists of already sorted runs.
+///
+/// TimSort (Peters, 2002) is a stable, adaptive merge sort:
+///
+/// - The data is scanned for natural runs, which are either non-descending or strictly descending. Descending runs
+///   are reversed in place. Runs shorter than `minrun` (32 to 64 elements) are extended with binary insertion sort.
+/// - The runs are pushed on a stack, and adjacent runs are merged such that the stack invariants
+///   `run[i - 2] > run[i - 1] + run[i]` and `run[i - 1] > run[i]` hold for the whole stack, which keeps the merges
+///   balanced. The original rule only checked the top of the stack, which de Gouw et al. (2015) showed could break
+///   the invariants deeper down; this implementation includes their fix.
+/// - Before a merge, the elements of the first run that are already in place are skipped, and so are those of the
+///   second run. The shorter of the two runs is moved to a buffer and merged from the matching end.
+/// - When one run keeps winning during a merge, the merge gallops: it searches exponentially for where the next
+///   element of the other run belongs, and moves all elements up to it at once.
+///
+/// It performs `O(n)` on sorted, reversed, or otherwise run-structured data, and `O(n log n)` in the worst case. The
+/// buffer holds up to `n / 2` elements, and the elements are moved rather than cloned.
+pub fn tim_sort<A: Ord>(data: &mut [A]) {
+    tim_sort_by(data, |a, b| a.cmp(b))
+}
+
+/// Sorts the data with TimSort, using the comparator function to order the elements.
+pub fn tim_sort_by<A, F: FnMut(&A, &A) -> Ordering>(data: &mut [A], compare: F) {
+    tim_sort_observed_by(data, compare, &NoObserver)
+}
+
+/// Sorts the data with TimSort, ordering the elements by the keys the function extracts from them.
+pub fn tim_sort_by_key<A, K: Ord, F: FnMut(&A) -> K>(data: &mut [A], mut key: F) {
+    tim_sort_by(data, |a, b| key(a).cmp(&key(b)))
+}
+
+/// Sorts the data with TimSort, reporting the comparisons, swaps and writes to the observer.
+///
+/// Reversing a descending run swaps its elements, and binary insertion writes the elements it shifts. A merge
+/// writes every element of the two runs, after the elements that are already in place are skipped. The moves to
+/// the buffer are not counted.
+pub fn tim_sort_observed<A: Ord, O: SortObserver<A>>(data: &mut [A], observer: &O) {
+    tim_sort_observed_by(data, |a, b| {
+        observer.compared();
+        a.cmp(b)
+    }, observer)
+}
+
+/// Sorts the data with TimSort, using the comparator function to order the elements, and reporting the swaps and
+/// writes to the observer.
+pub fn tim_sort_observed_by<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], mut compare: F, observer: &O) {
+    let compare = &mut compare;
+    let length = data.len();
+    if length < 2 {
+        return;
+    }
+
+    if length < MIN_MERGE {
+        let run = count_run(data, compare, observer);
+        binary_insertion_sort(data, run, compare, observer);
+        return;
+    }
+
+    let min_run = min_run_length(length);
+    let mut buffer: Vec<A> = Vec::with_capacity(length / 2);
+    let mut runs: Vec<Run> = Vec::new();
+    let mut min_gallop = MIN_GALLOP;
+    let mut start = 0;
+
+    while start < length {
+        let mut run = count_run(&mut data[start..], compare, observer);
+
+        if run < min_run {
+            let end = min(length, start + min_run);
+            binary_insertion_sort(&mut data[start..end], run, compare, observer);
+            run = end - start;
+        }
+
+        runs.push(Run { start: start, length: run });
+        start += run;
+
+        merge_collapse(data, &mut runs, buffer.as_mut_ptr(), compare, observer, &mut min_gallop);
+    }
+
+    // Merge the remaining runs, which shrink from the bottom to the top of the stack.
+    while runs.len() > 1 {
+        let mut n = runs.len() - 2;
+        if n > 0 && runs[n - 1].length < runs[n + 1].length {
+            n -= 1;
+        }
+
+        merge_at(data, &mut runs, n, buffer.as_mut_ptr(), compare, observer, &mut min_gallop);
+    }
+}
+
+/// Returns the minimum run length: the six most significant bits of the length, plus one if any of the remaining
+/// bits is set. The number of runs is then a power of two, or slightly less, which balances the final merges.
+fn min_run_length(mut length: usize) -> usize {
+    let mut remainder = 0;
+    while length >= MIN_MERGE {
+        remainder |= length & 1;
+        length >>= 1;
+    }
+
+    length + remainder
+}
+
+/// Returns the length of the run at the start of the data, and reverses it if it is descending.
+///
+/// Descending runs have to be strictly descending, so that reversing them keeps equal elements in order.
+fn count_run<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(data: &mut [A], compare: &mut F, observer: &O) -> usize {
+    let length = data.len();
+    if length < 2 {
+        return length;
+    }
+
+    let mut end = 2;
+    if compare(&data[1], &data[0]) == Ordering::Less {
+        while end < length && compare(&data[end], &data[end - 1]) == Ordering::Less {
+            end += 1;
+        }
+        data[..end].reverse();
+        for i in 0 .. end / 2 {
+            observer.swapped(data, i, end - 1 - i);
+        }
+    } else {
+        while end < length && compare(&data[end], &data[end - 1]) != Ordering::Less {
+            end += 1;
+        }
+    }
+
+    end
+}
+
+/// Sorts the data with binary insertion sort, given that `data[..sorted]` is already sorted.
+///
+/// Every element is placed after the equal elements before it, which keeps the sort stable.
+fn binary_insertion_sort<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(
+    data: &mut [A],
+    sorted: usize,
+    compare: &mut F,
+    observer: &O
+) {
+    for i in sorted.max(1) .. data.len() {
+        let (mut low, mut high) = (0, i);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if compare(&data[i], &data[middle]) == Ordering::Less {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        if low < i {
+            data[low ..= i].rotate_right(1);
+            observer.wrote(data, low .. i + 1);
+        }
+    }
+}
+
+/// Returns the number of elements at the start of the sorted data for which `before` returns true.
+///
+/// The search gallops, checking the elements at offsets 0, 1, 3, 7, ... from the start, or from the end if
+/// `from_end` is set, and then binary searches the last gap. This takes `O(log k)` comparisons when the result is
+/// `k` elements away from where the search starts.
+fn gallop<A, P: FnMut(&A) -> bool>(data: &[A], from_end: bool, mut before: P) -> usize {
+    let length = data.len();
+    let (mut low, mut high);
+
+    if from_end {
+        if length == 0 || before(&data[length - 1]) {
+            return length;
+        }
+
+        let (mut last, mut offset) = (0, 1);
+        while offset < length && !before(&data[length - 1 - offset]) {
+            last = offset;
+            offset = offset * 2 + 1;
+        }
+
+        low = length.saturating_sub(offset);
+        high = length - 1 - last;
+    } else {
+        if length == 0 || !before(&data[0]) {
+            return 0;
+        }
+
+        let (mut last, mut offset) = (0, 1);
+        while offset < length && before(&data[offset]) {
+            last = offset;
+            offset = offset * 2 + 1;
+        }
+
+        low = last + 1;
+        high = min(offset, length);
+    }
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if before(&data[middle]) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    low
+}
+
+/// Merges runs until the stack invariants hold again for the whole stack.
+fn merge_collapse<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(
+    data: &mut [A],
+    runs: &mut Vec<Run>,
+    buffer: *mut A,
+    compare: &mut F,
+    observer: &O,
+    min_gallop: &mut usize
+) {
+    while runs.len() > 1 {
+        let mut n = runs.len() - 2;
+
+        // The 2015 fix: checking the run below as well keeps the invariants from breaking deeper in the stack.
+        if (n > 0 && runs[n - 1].length <= runs[n].length + runs[n + 1].length) ||
+            (n > 1 && runs[n - 2].length <= runs[n - 1].length + runs[n].length) {
+            if runs[n - 1].length < runs[n + 1].length {
+                n -= 1;
+            }
+        } else if runs[n].length > runs[n + 1].length {
+            break;
+        }
+
+        merge_at(data, runs, n, buffer, compare, observer, min_gallop);
+    }
+
+    debug_assert!((2 .. runs.len()).all(|i| runs[i - 2].length > runs[i - 1].length + runs[i].length));
+    debug_assert!((1 .. runs.len()).all(|i| runs[i - 1].length > runs[i].length));
+}
+
+/// Merges the runs `n` and `n + 1` of the stack.
+fn merge_at<A, F: FnMut(&A, &A) -> Ordering, O: SortObserver<A>>(
+    data: &mut [A],
+    runs: &mut Vec<Run>,
+    n: usize,
+    buffer: *mut A,
+    compare: &mut F,
+    observer: &O,
+    min_gallop: &mut usize
+) {
+    let (first, second) = (runs[n], runs[n + 1]);
+    runs[n].length += second.length;
+    runs.remove(n + 1);
+
+    let data = &mut data[first.start .. second.start + second.length];
+
+    // The elements of the first run that are not greater than the first element of the second run are in place.
+    let skipped = {
+        let (left, right) = data.split_at(first.length);
+        gallop(left, false, |element| compare(element, &right[0]) != Ordering::Greater)
+    };
+    if skipped == first.length {
+        return;
+    }
+
+    let data = &mut data[skipped..];
+    let first_length = first.length - skipped;
+
+    // The elements of the second run that are not less than the last element of the first run are in place.
+    let second_length = {
+        let (left, right) = data.split_at(first_length);
+        gallop(right, true, |element| compare(element, &left[first_length - 1]) == Ordering::Less)
+    };
+
+    let data = &mut data[.. first_length + second_length];
+    unsafe {
+        if first_length <= second_length {
+            merge_low(data, first_length, buffer, compare, min_gallop);
+        } else {
+            merge_high(data, first_length, buffer, compare, min_gallop);
+        }
+    }
+    observer.wrote(data, 0 .. data.len());
+}
+
+/// The elements of a run that are still in the buffer.
+///
+/// The elements are moved to the destination when the hole is dropped, also when the comparator panics, so that
+/// every element is in the data exactly once.
+struct MergeHole<A> {
+    start: *mut A,
+    end: *mut A,
+    destination: *mut A,
+}
+
+impl<A> MergeHole<A> {
+    fn length(&self) -> usize {
+        unsafe { self.end.offset_from(self.start) as usize }
+    }
+}
+
+impl<A> Drop for MergeHole<A> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::copy_nonoverlapping(self.start, self.destination, self.length());
+        }
+    }
+}
+
+/// Merges `data[..middle]` and `data[middle..]` from the front, with the first run moved to the buffer.
+///
+/// The first run must not be longer than the second, and the first element of the second run must be less than
+/// the first element of the first run.
+unsafe fn merge_low<A, F: FnMut(&A, &A) -> Ordering>(
+    data: &mut [A],
+    middle: usize,
+    buffer: *mut A,
+    compare: &mut F,
+    min_gallop: &mut usize
+) {
+    let base = data.as_mut_ptr();
+    let mut right = base.add(middle);
+    let right_end = base.add(data.len());
+
+    ptr::copy_nonoverlapping(base, buffer, middle);
+    let mut hole = MergeHole {
+        start: buffer,
+        end: buffer.add(middle),
+        destination: base,
+    };
+
+    // The destination is always right before the rest of the second run, so the rest of the first run fits
+    // between them.
+    'merge: loop {
+        let (mut left_wins, mut right_wins) = (0, 0);
+
+        // Take one element at a time, until one of the runs keeps winning.
+        loop {
+            if hole.start == hole.end || right == right_end {
+                break 'merge;
+            }
+
+            if compare(&*right, &*hole.start) == Ordering::Less {
+                ptr::copy_nonoverlapping(right, hole.destination, 1);
+                right = right.add(1);
+                right_wins += 1;
+                left_wins = 0;
+            } else {
+                ptr::copy_nonoverlapping(hole.start, hole.destination, 1);
+                hole.start = hole.start.add(1);
+                left_wins += 1;
+                right_wins = 0;
+            }
+            hole.destination = hole.destination.add(1);
+
+            if left_wins >= *min_gallop || right_wins >= *min_gallop {
+                break;
+            }
+        }
+
+        // Gallop, until neither run wins by a long streak anymore.
+        loop {
+            if hole.start == hole.end || right == right_end {
+                break 'merge;
+            }
+
+            let left = slice::from_raw_parts(hole.start, hole.length());
+            left_wins = gallop(left, false, |element| compare(&*right, element) != Ordering::Less);
+            ptr::copy_nonoverlapping(hole.start, hole.destination, left_wins);
+            hole.start = hole.start.add(left_wins);
+            hole.destination = hole.destination.add(left_wins);
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            ptr::copy_nonoverlapping(right, hole.destination, 1);
+            right = right.add(1);
+            hole.destination = hole.destination.add(1);
+            if right == right_end {
+                break 'merge;
+            }
+
+            let remaining = slice::from_raw_parts(right, right_end.offset_from(right) as usize);
+            right_wins = gallop(remaining, false, |element| compare(element, &*hole.start) == Ordering::Less);
+            ptr::copy(right, hole.destination, right_wins);
+            right = right.add(right_wins);
+            hole.destination = hole.destination.add(right_wins);
+            if right == right_end {
+                break 'merge;
+            }
+
+            ptr::copy_nonoverlapping(hole.start, hole.destination, 1);
+            hole.start = hole.start.add(1);
+            hole.destination = hole.destination.add(1);
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            // Galloping pays off, so start it sooner next time.
+            if *min_gallop > 1 {
+                *min_gallop -= 1;
+            }
+
+            if left_wins < MIN_GALLOP && right_wins < MIN_GALLOP {
+                *min_gallop += 2;
+                break;
+            }
+        }
+    }
+
+    // Dropping the hole moves the rest of the first run in place. The rest of the second run already is.
+}
+
+/// Merges `data[..middle]` and `data[middle..]` from the back, with the second run moved to the buffer.
+///
+/// The second run must be shorter than the first, and the last element of the first run must be greater than the
+/// last element of the second run.
+unsafe fn merge_high<A, F: FnMut(&A, &A) -> Ordering>(
+    data: &mut [A],
+    middle: usize,
+    buffer: *mut A,
+    compare: &mut F,
+    min_gallop: &mut usize
+) {
+    let base = data.as_mut_ptr();
+    let second_length = data.len() - middle;
+
+    ptr::copy_nonoverlapping(base.add(middle), buffer, second_length);
+
+    // The destination of the hole is the end of the rest of the first run, and the merged elements are placed
+    // right after the hole, from the back.
+    let mut hole = MergeHole {
+        start: buffer,
+        end: buffer.add(second_length),
+        destination: base.add(middle),
+    };
+
+    'merge: loop {
+        let (mut left_wins, mut right_wins) = (0, 0);
+
+        loop {
+            if hole.destination == base || hole.start == hole.end {
+                break 'merge;
+            }
+
+            let output = hole.destination.add(hole.length() - 1);
+            let left_last = hole.destination.sub(1);
+            let right_last = hole.end.sub(1);
+
+            // Taking from the second run on ties keeps equal elements in their original order.
+            if compare(&*right_last, &*left_last) == Ordering::Less {
+                ptr::copy_nonoverlapping(left_last, output, 1);
+                hole.destination = left_last;
+                left_wins += 1;
+                right_wins = 0;
+            } else {
+                ptr::copy_nonoverlapping(right_last, output, 1);
+                hole.end = right_last;
+                right_wins += 1;
+                left_wins = 0;
+            }
+
+            if left_wins >= *min_gallop || right_wins >= *min_gallop {
+                break;
+            }
+        }
+
+        loop {
+            if hole.destination == base || hole.start == hole.end {
+                break 'merge;
+            }
+
+            // The elements of the first run that are greater than the last element of the second run.
+            let left = slice::from_raw_parts(base, hole.destination.offset_from(base) as usize);
+            let right_last = hole.end.sub(1);
+            left_wins = left.len() - gallop(left, true, |element| compare(&*right_last, element) != Ordering::Less);
+            hole.destination = hole.destination.sub(left_wins);
+            ptr::copy(hole.destination, hole.destination.add(hole.length()), left_wins);
+            if hole.destination == base {
+                break 'merge;
+            }
+
+            ptr::copy_nonoverlapping(right_last, hole.destination.add(hole.length() - 1), 1);
+            hole.end = right_last;
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            // The elements of the second run that are not less than the last element of the first run.
+            let right = slice::from_raw_parts(hole.start, hole.length());
+            let left_last = hole.destination.sub(1);
+            right_wins = right.len() - gallop(right, true, |element| compare(element, &*left_last) == Ordering::Less);
+            hole.end = hole.end.sub(right_wins);
+            ptr::copy_nonoverlapping(hole.end, hole.destination.add(hole.length()), right_wins);
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            ptr::copy_nonoverlapping(left_last, hole.destination.add(hole.length() - 1), 1);
+            hole.destination = left_last;
+            if hole.destination == base {
+                break 'merge;
+            }
+
+            if *min_gallop > 1 {
+                *min_gallop -= 1;
+            }
+
+            if left_wins < MIN_GALLOP && right_wins < MIN_GALLOP {
+                *min_gallop += 2;
+                break;
+            }
+        }
+    }
+
+    // Dropping the hole moves the rest of the second run in place, right after the rest of the first run.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+    use std::rc::Rc;
+    use test::Bencher;
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    use test_helpers::random_data;
+    use algorithms::merge_sort::merge_sort;
+    use algorithms::sort_stats::{SortStats, SortTrace};
+
+    static BENCH_SIZE: usize = 100_000usize;
+
+    /// Returns data made of sorted and reversed runs of random lengths.
+    fn run_data(size: usize, max_run: usize) -> Vec<u32> {
+        let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+        let mut data = Vec::with_capacity(size);
+
+        while data.len() < size {
+            let run = min(size - data.len(), rng.gen_range(1, max_run + 1));
+            let mut values: Vec<u32> = (0 .. run).map(|_| rng.gen_range(0, 1_000_000)).collect();
+            values.sort();
+            if rng.gen() {
+                values.reverse();
+            }
+            data.extend(values);
+        }
+
+        data
+    }
+
+    fn assert_sorts(data: Vec<u32>) {
+        let mut expected = data.clone();
+        expected.sort();
+
+        let mut sorted = data;
+        tim_sort(sorted.as_mut_slice());
+        assert_eq!(expected, sorted);
+    }
+
+    #[test]
+    fn test_tim_sort() {
+        let mut data = vec![9, 8, 1, 5, 3, 16, 2, 0, 4];
+        tim_sort(data.as_mut_slice());
+        assert_eq!([0, 1, 2, 3, 4, 5, 8, 9, 16], data.as_slice());
+
+        for &size in [0, 1, 2, 63, 64, 65, 1_000, 10_000, 100_000].iter() {
+            assert_sorts(random_data(size, u32::MAX));
+            assert_sorts(random_data(size, 5));
+            assert_sorts(run_data(size, 1_000));
+            assert_sorts(run_data(size, 10));
+            assert_sorts((0 .. size as u32).collect());
+            assert_sorts((0 .. size as u32).rev().collect());
+            assert_sorts(vec![7; size]);
+        }
+    }
+
+    #[test]
+    fn test_tim_sort_is_stable() {
+        for &size in [50, 1_000, 100_000].iter() {
+            let mut data: Vec<(u32, usize)> = run_data(size, 500).into_iter()
+                .enumerate()
+                .map(|(i, value)| (value % 100, i))
+                .collect();
+            let mut expected = data.clone();
+            expected.sort_by_key(|&(key, _)| key);
+
+            tim_sort_by_key(data.as_mut_slice(), |&(key, _)| key);
+            assert_eq!(expected, data);
+        }
+    }
+
+    /// Sorted data is a single run, and reversed data a single descending run.
+    #[test]
+    fn test_tim_sort_observed() {
+        let mut data: Vec<u32> = (0 .. 1_000).collect();
+        let stats = SortStats::new();
+        tim_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!((999, 0, 0), (stats.comparisons(), stats.swaps(), stats.writes()));
+
+        let mut data: Vec<u32> = (0 .. 1_000).rev().collect();
+        let stats = SortStats::new();
+        tim_sort_observed(data.as_mut_slice(), &stats);
+        assert_eq!((999, 500, 0), (stats.comparisons(), stats.swaps(), stats.writes()));
+
+        let mut data = run_data(1_000, 100);
+        let trace = SortTrace::new(&data);
+        tim_sort_observed(data.as_mut_slice(), &trace);
+        assert!(data.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(Some(&data), trace.states().last());
+    }
+
+    #[test]
+    fn test_min_run_length() {
+        assert_eq!(63, min_run_length(63));
+        assert_eq!(32, min_run_length(64));
+        assert_eq!(33, min_run_length(65));
+        assert_eq!(32, min_run_length(2048));
+        assert_eq!(33, min_run_length(2049));
+    }
+
+    #[test]
+    fn test_gallop() {
+        let data = [1, 2, 2, 2, 3, 5, 8, 8, 9];
+        for key in 0 .. 11 {
+            let less = data.iter().filter(|&&x| x < key).count();
+            let not_greater = data.iter().filter(|&&x| x <= key).count();
+
+            for &from_end in [false, true].iter() {
+                assert_eq!(less, gallop(&data, from_end, |&x| x < key));
+                assert_eq!(not_greater, gallop(&data, from_end, |&x| x <= key));
+            }
+        }
+    }
+
+    /// A panicking comparator leaves every element in the data exactly once.
+    #[test]
+    fn test_tim_sort_panic_safety() {
+        let value = Rc::new(());
+        for &panic_at in [100, 1_000, 5_000].iter() {
+            let mut data: Vec<(u32, Rc<()>)> = run_data(1_000, 100).into_iter().map(|v| (v, value.clone())).collect();
+            let mut keys: Vec<u32> = data.iter().map(|element| element.0).collect();
+            let mut comparisons = 0;
+
+            let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                tim_sort_by(data.as_mut_slice(), |a, b| {
+                    comparisons += 1;
+                    if comparisons == panic_at {
+                        panic!("comparator failure");
+                    }
+                    a.0.cmp(&b.0)
+                });
+            }));
+            assert!(result.is_err());
+
+            let mut after: Vec<u32> = data.iter().map(|element| element.0).collect();
+            keys.sort();
+            after.sort();
+            assert_eq!(keys, after);
+        }
+
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[bench]
+    fn tim_sort_runs(b: &mut Bencher) {
+        let data = run_data(BENCH_SIZE, 1_000);
+        b.iter(|| tim_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn merge_sort_runs(b: &mut Bencher) {
+        let data = run_data(BENCH_SIZE, 1_000);
+        b.iter(|| merge_sort(data.as_slice()))
+    }
+
+    #[bench]
+    fn tim_sort_nearly_sorted(b: &mut Bencher) {
+        let mut data: Vec<u32> = (0 .. BENCH_SIZE as u32).collect();
+        for i in 0 .. 100 {
+            data.swap(i * 997 % BENCH_SIZE, i * 7919 % BENCH_SIZE);
+        }
+        b.iter(|| tim_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn merge_sort_nearly_sorted(b: &mut Bencher) {
+        let mut data: Vec<u32> = (0 .. BENCH_SIZE as u32).collect();
+        for i in 0 .. 100 {
+            data.swap(i * 997 % BENCH_SIZE, i * 7919 % BENCH_SIZE);
+        }
+        b.iter(|| merge_sort(data.as_slice()))
+    }
+
+    #[bench]
+    fn tim_sort_random(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| tim_sort(data.clone().as_mut_slice()))
+    }
+
+    #[bench]
+    fn merge_sort_random(b: &mut Bencher) {
+        let data = random_data(BENCH_SIZE, u32::MAX);
+        b.iter(|| merge_sort(data.as_slice()))
+    }
+}
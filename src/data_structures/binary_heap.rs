@@ -60,22 +60,29 @@ impl<A: Ord> BinaryHeap<A> {
     fn sift_down(&mut self, current_index: usize, bottom_index: usize) {
         let first_index = index::first_child(current_index);
 
-        if first_index != bottom_index {
-            if self.data[current_index] < self.data[first_index] {
-                self.data.swap(current_index, first_index);
-                self.sift_down(first_index, bottom_index);
+        if first_index <= bottom_index {
+            let second_index = index::second_child(current_index);
+            let has_second_child = second_index <= bottom_index;
+
+            let child_index = if has_second_child && self.data[first_index] < self.data[second_index] {
+                second_index
             } else {
-                let second_index = index::second_child(current_index);
-                let has_children = second_index != bottom_index;
+                first_index
+            };
 
-                if has_children && self.data[current_index] < self.data[second_index] {
-                    self.data.swap(current_index, second_index);
-                    self.sift_down(second_index, bottom_index);
-                }
+            if self.data[current_index] < self.data[child_index] {
+                self.data.swap(current_index, child_index);
+                self.sift_down(child_index, bottom_index);
             }
         }
     }
 
+    /// Returns the most important element without removing it.
+    #[inline]
+    pub fn peek(&self) -> Option<&A> {
+        self.data.first()
+    }
+
     /// Returns the length of this heap.
     pub fn length(&self) -> usize {
         self.data.len()
@@ -100,6 +107,24 @@ mod tests {
         assert_eq!(None, bh.pop());
     }
 
+    #[test]
+    fn pops_in_order() {
+        let mut bh = BinaryHeap::new();
+
+        for &x in [5u32, 3, 9, 1, 7, 2, 8, 6, 4, 0, 11, 10].iter() {
+            bh.push(x);
+        }
+
+        assert_eq!(Some(&11u32), bh.peek());
+
+        let mut popped = Vec::new();
+        while let Some(x) = bh.pop() {
+            popped.push(x);
+        }
+
+        assert_eq!(vec![11u32, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0], popped);
+    }
+
     #[bench]
     fn pushing(b: &mut Bencher) {
         b.iter(|| {
@@ -0,0 +1,435 @@
+use std::cmp::max;
+use algorithms::hash::{HashFunction, Murmur3};
+use data_structures::bloom_filter::FromBytesError;
+use rustc_serialize::{Encodable, Encoder, Decodable, Decoder};
+
+/// The number of bits in a block, which is the size of a cache line on most platforms.
+static BLOCK_BITS: u32 = 512u32;
+
+/// The version of the binary format produced by `BlockedBloomFilter::to_bytes()`.
+static FORMAT_VERSION: u8 = 1u8;
+
+/// The size of the binary header: a version byte followed by three little-endian `u32`s.
+static HEADER_LENGTH: usize = 13usize;
+
+/// The size of a serialized block: eight little-endian `u64`s.
+static BLOCK_LENGTH: usize = 64usize;
+
+/// A block of 512 bits, which is aligned to 64 bytes so that it never straddles two cache lines.
+#[repr(align(64))]
+#[derive(Clone, Copy)]
+struct Block([u64; 8]);
+
+/// An implementation of a blocked bloom filter (Putze et al., 2007).
+///
+/// A regular bloom filter touches `k` random cache lines per query. A blocked bloom filter first selects a
+/// 512-bit block with one hash, and then sets or tests all `k` bits of the value within that block. Every
+/// query thus touches a single cache line, at the cost of a slightly higher false-positive rate.
+pub struct BlockedBloomFilter<H = Murmur3> {
+    blocks: Vec<Block>,
+    expected_length: u32,
+    hash_count: u32,
+    hash_function: H,
+}
+
+impl BlockedBloomFilter {
+    /// Constructs a new blocked bloom filter.
+    ///
+    /// You must specify the number of bits in the bloom filter, and also you should specify the number of items you
+    /// expect to add. The number of bits is rounded up to a multiple of 512.
+    pub fn with_capacity(capacity: u32, expected_length: u32) -> BlockedBloomFilter {
+        BlockedBloomFilter::with_capacity_and_hasher(capacity, expected_length, Murmur3)
+    }
+
+    /// Constructs a new blocked bloom filter that holds `expected_length` items at the given false-positive rate.
+    ///
+    /// The number of bits is derived from `m = -n * ln(p) / ln(2)^2`. Panics unless the rate is within `(0, 1)`.
+    pub fn with_rate(false_positive_rate: f32, expected_length: u32) -> BlockedBloomFilter {
+        BlockedBloomFilter::with_rate_and_hasher(false_positive_rate, expected_length, Murmur3)
+    }
+
+    /// Deserializes a blocked bloom filter that was serialized with `to_bytes()`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<BlockedBloomFilter, FromBytesError> {
+        BlockedBloomFilter::from_bytes_and_hasher(bytes, Murmur3)
+    }
+}
+
+impl<H: HashFunction> BlockedBloomFilter<H> {
+    /// Constructs a new blocked bloom filter that uses the given hash function.
+    pub fn with_capacity_and_hasher(capacity: u32, expected_length: u32, hash_function: H) -> BlockedBloomFilter<H> {
+        // Rounding `u32::MAX` bits up to whole blocks takes 2^32 bits, so the sizes are computed in `u64`.
+        let block_count = max(1, (capacity as u64).div_ceil(BLOCK_BITS as u64));
+        let hash_count = (block_count * BLOCK_BITS as u64 / max(1, expected_length) as u64) as f32 * 2.0f32.ln();
+
+        BlockedBloomFilter {
+            blocks: vec![Block([0u64; 8]); block_count as usize],
+            expected_length: expected_length,
+            hash_count: max(1, hash_count.ceil() as u32),
+            hash_function: hash_function,
+        }
+    }
+
+    /// Constructs a new blocked bloom filter for the given false-positive rate that uses the given hash function.
+    pub fn with_rate_and_hasher(
+        false_positive_rate: f32,
+        expected_length: u32,
+        hash_function: H
+    ) -> BlockedBloomFilter<H> {
+        // Written so that NaN is rejected as well.
+        if !(false_positive_rate > 0f32 && false_positive_rate < 1f32) {
+            panic!("the false-positive rate {} has to be within (0, 1)", false_positive_rate);
+        }
+
+        let ln2 = 2.0f32.ln();
+        let bits = -(expected_length as f32) * false_positive_rate.ln() / (ln2 * ln2);
+
+        BlockedBloomFilter::with_capacity_and_hasher(max(1, bits.ceil() as u32), max(1, expected_length), hash_function)
+    }
+
+    /// Returns the number of bits in the bloom filter, which may be up to 2^32.
+    pub fn capacity(&self) -> u64 {
+        self.blocks.len() as u64 * BLOCK_BITS as u64
+    }
+
+    /// Returns the number of items the bloom filter was sized for.
+    pub fn expected_length(&self) -> u32 {
+        self.expected_length
+    }
+
+    /// Returns the expected false-positive rate.
+    ///
+    /// This is the rate of a regular bloom filter of the same size. The actual rate is slightly higher, because
+    /// the values are not spread evenly over the blocks.
+    pub fn expected_false_positive_rate(&self) -> f32 {
+        // (1 - e^(-k * n / m)) ^ k
+        let pre = (-(self.hash_count as f32) * self.expected_length as f32) / self.capacity() as f32;
+        (1f32 - pre.exp()).powi(self.hash_count as i32)
+    }
+
+    /// Returns the block index, and the two hashes from which the bit positions within the block are derived.
+    #[inline]
+    fn hashes(&self, data: &[u8]) -> (usize, u32, u32) {
+        let block = (self.hash_function.hash(data, 0) % self.blocks.len() as u64) as usize;
+        let hash = self.hash_function.hash(data, 1) as u32;
+
+        (block, hash & 0xffff, (hash >> 16) | 1)
+    }
+
+    /// Pushes a new value to the bloom filter.
+    pub fn push(&mut self, data: &[u8]) {
+        let (block, a, b) = self.hashes(data);
+        let block = &mut self.blocks[block].0;
+
+        for i in 0 .. self.hash_count {
+            let bit = a.wrapping_add(i.wrapping_mul(b)) % BLOCK_BITS;
+            block[(bit / 64) as usize] |= 1u64 << (bit % 64);
+        }
+    }
+
+    /// Clears the bloom filter.
+    pub fn clear(&mut self) {
+        for block in self.blocks.iter_mut() {
+            *block = Block([0u64; 8]);
+        }
+    }
+
+    /// Returns false if the data was definitely not added to the bloom filter, and true if it may have been.
+    pub fn contains(&self, data: &[u8]) -> bool {
+        let (block, a, b) = self.hashes(data);
+        let block = &self.blocks[block].0;
+
+        (0 .. self.hash_count).all(|i| {
+            let bit = a.wrapping_add(i.wrapping_mul(b)) % BLOCK_BITS;
+            block[(bit / 64) as usize] & (1u64 << (bit % 64)) != 0
+        })
+    }
+
+    /// Returns whether the two bloom filters have identical parameters, and can thus be combined.
+    pub fn is_compatible(&self, other: &BlockedBloomFilter<H>) -> bool {
+        self.blocks.len() == other.blocks.len() &&
+        self.expected_length == other.expected_length &&
+        self.hash_count == other.hash_count
+    }
+
+    /// Adds every value of the other bloom filter to this one.
+    ///
+    /// The result is identical to a bloom filter that had all of the values pushed to it.
+    pub fn union(&mut self, other: &BlockedBloomFilter<H>) {
+        self.assert_compatible(other);
+
+        for (block, other) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+            for (word, other) in block.0.iter_mut().zip(other.0.iter()) {
+                *word |= *other;
+            }
+        }
+    }
+
+    /// Keeps only the bits that are set in both bloom filters.
+    ///
+    /// The result contains every value that was pushed to both filters, but its false-positive rate may be
+    /// higher than that of a bloom filter that had only the common values pushed to it.
+    pub fn intersect(&mut self, other: &BlockedBloomFilter<H>) {
+        self.assert_compatible(other);
+
+        for (block, other) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+            for (word, other) in block.0.iter_mut().zip(other.0.iter()) {
+                *word &= *other;
+            }
+        }
+    }
+
+    fn assert_compatible(&self, other: &BlockedBloomFilter<H>) {
+        if !self.is_compatible(other) {
+            panic!(
+                "incompatible blocked bloom filters: ({}, {}, {}) and ({}, {}, {})",
+                self.capacity(),
+                self.expected_length,
+                self.hash_count,
+                other.capacity(),
+                other.expected_length,
+                other.hash_count
+            );
+        }
+    }
+
+    /// Serializes the bloom filter into a versioned binary format.
+    ///
+    /// The format is a version byte, followed by the number of blocks, the expected length and the hash count as
+    /// little-endian `u32`s, followed by the words of every block as little-endian `u64`s.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.blocks.len() * BLOCK_LENGTH);
+
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&(self.blocks.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.expected_length.to_le_bytes());
+        bytes.extend_from_slice(&self.hash_count.to_le_bytes());
+
+        for block in self.blocks.iter() {
+            for word in block.0.iter() {
+                bytes.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// Deserializes a blocked bloom filter that was serialized with `to_bytes()`, and uses the given hash function.
+    ///
+    /// The hash function is not part of the binary format, so it has to be the one the bloom filter was built with.
+    pub fn from_bytes_and_hasher(bytes: &[u8], hash_function: H) -> Result<BlockedBloomFilter<H>, FromBytesError> {
+        if bytes.is_empty() {
+            return Err(FromBytesError::InvalidLength);
+        }
+
+        if bytes[0] != FORMAT_VERSION {
+            return Err(FromBytesError::UnsupportedVersion(bytes[0]));
+        }
+
+        if bytes.len() < HEADER_LENGTH {
+            return Err(FromBytesError::InvalidLength);
+        }
+
+        let block_count = u32_from_bytes(&bytes[1..]) as usize;
+        let expected_length = u32_from_bytes(&bytes[5..]);
+        let hash_count = u32_from_bytes(&bytes[9..]);
+
+        if block_count == 0 || hash_count == 0 {
+            return Err(FromBytesError::InvalidHeader);
+        }
+
+        let words = &bytes[HEADER_LENGTH..];
+        if block_count.checked_mul(BLOCK_LENGTH) != Some(words.len()) {
+            return Err(FromBytesError::InvalidLength);
+        }
+
+        let blocks = words.chunks(BLOCK_LENGTH).map(|chunk| {
+            let mut block = Block([0u64; 8]);
+            for (word, bytes) in block.0.iter_mut().zip(chunk.chunks(8)) {
+                *word = u64::from_le_bytes([
+                    bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]
+                ]);
+            }
+            block
+        }).collect();
+
+        Ok(BlockedBloomFilter {
+            blocks: blocks,
+            expected_length: expected_length,
+            hash_count: hash_count,
+            hash_function: hash_function,
+        })
+    }
+}
+
+fn u32_from_bytes(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+/// Encodes the blocked bloom filter as the byte sequence produced by `to_bytes()`.
+impl<H: HashFunction> Encodable for BlockedBloomFilter<H> {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        self.to_bytes().encode(s)
+    }
+}
+
+impl Decodable for BlockedBloomFilter {
+    fn decode<D: Decoder>(d: &mut D) -> Result<BlockedBloomFilter, D::Error> {
+        let bytes: Vec<u8> = Decodable::decode(d)?;
+        BlockedBloomFilter::from_bytes(&bytes).map_err(|e| d.error(&e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::{Bencher, black_box};
+    use data_structures::bloom_filter::BloomFilter;
+    use test_helpers::key;
+
+    /// The number of keys the benchmarked filters are sized for, so that they do not fit in the CPU caches.
+    static BENCH_LENGTH: u32 = 10_000_000u32;
+
+    #[test]
+    fn basic_tests() {
+        let mut filter = BlockedBloomFilter::with_capacity(10, 5);
+        let a = vec![1, 2, 3, 4];
+        assert_eq!(512, filter.capacity());
+        assert_eq!(false, filter.contains(a.as_slice()));
+        filter.push(a.as_slice());
+        assert_eq!(true, filter.contains(a.as_slice()));
+        filter.clear();
+        assert_eq!(false, filter.contains(a.as_slice()));
+    }
+
+    #[test]
+    fn false_positive_rate() {
+        let mut filter = BlockedBloomFilter::with_rate(0.01, 10_000);
+
+        for i in 0 .. 10_000 {
+            filter.push(&key(i));
+        }
+
+        for i in 0 .. 10_000 {
+            assert!(filter.contains(&key(i)));
+        }
+
+        let false_positives = (10_000 .. 110_000).filter(|&i| filter.contains(&key(i))).count();
+        assert!(false_positives < 2_000, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn blocks_are_cache_aligned() {
+        for &capacity in [512, 1_000, 100_000].iter() {
+            let filter = BlockedBloomFilter::with_capacity(capacity, 100);
+            assert_eq!(0, filter.blocks.as_ptr() as usize % 64);
+
+            let decoded = BlockedBloomFilter::from_bytes(&filter.to_bytes()).unwrap();
+            assert_eq!(0, decoded.blocks.as_ptr() as usize % 64);
+        }
+    }
+
+    #[test]
+    fn with_rate_out_of_range() {
+        use std::panic;
+
+        for &rate in [0.0, 1.0, -0.01, 1.5, f32::NAN].iter() {
+            assert!(panic::catch_unwind(|| BlockedBloomFilter::with_rate(rate, 1000)).is_err(), "rate {}", rate);
+        }
+    }
+
+    #[test]
+    fn large_capacity() {
+        // The largest capacity rounds up to 2^32 bits, which does not fit in a `u32`.
+        let filter = BlockedBloomFilter::with_capacity(u32::MAX, 1 << 28);
+        assert_eq!(1u64 << 32, filter.capacity());
+        assert_eq!(12, filter.hash_count);
+
+        assert_eq!(512, BlockedBloomFilter::with_capacity(0, 0).capacity());
+    }
+
+    #[test]
+    fn union_and_intersect() {
+        let mut a = BlockedBloomFilter::with_rate(0.01, 100);
+        let mut b = BlockedBloomFilter::with_rate(0.01, 100);
+        assert!(a.is_compatible(&b));
+        assert!(!a.is_compatible(&BlockedBloomFilter::with_rate(0.01, 200)));
+
+        for i in 0 .. 50 {
+            a.push(&key(i));
+            b.push(&key(i + 25));
+        }
+
+        let mut union = BlockedBloomFilter::from_bytes(&a.to_bytes()).unwrap();
+        union.union(&b);
+        assert!((0 .. 75).all(|i| union.contains(&key(i))));
+
+        a.intersect(&b);
+        assert!((25 .. 50).all(|i| a.contains(&key(i))));
+        let false_positives = (0 .. 25).chain(50 .. 75).filter(|&i| a.contains(&key(i))).count();
+        assert!(false_positives < 10, "{} false positives", false_positives);
+    }
+
+    #[test]
+    #[should_panic]
+    fn union_incompatible() {
+        let mut a = BlockedBloomFilter::with_capacity(512, 10);
+        a.union(&BlockedBloomFilter::with_capacity(1_024, 10));
+    }
+
+    #[test]
+    fn serialization() {
+        use rustc_serialize::json;
+
+        let mut filter = BlockedBloomFilter::with_capacity(1_000, 100);
+        filter.push(&[1u8, 2, 3, 4]);
+
+        let bytes = filter.to_bytes();
+        assert_eq!([1u8, 2, 0, 0, 0, 100, 0, 0, 0, 7, 0, 0, 0], &bytes[..13]);
+        assert_eq!(13 + 2 * 64, bytes.len());
+
+        let decoded = BlockedBloomFilter::from_bytes(&bytes).unwrap();
+        assert!(decoded.is_compatible(&filter));
+        assert!(decoded.contains(&[1u8, 2, 3, 4]));
+        assert_eq!(bytes, decoded.to_bytes());
+
+        assert_eq!(Err(FromBytesError::UnsupportedVersion(2)), BlockedBloomFilter::from_bytes(&[2u8]).map(|_| ()));
+        assert_eq!(Err(FromBytesError::InvalidLength), BlockedBloomFilter::from_bytes(&bytes[..140]).map(|_| ()));
+        assert_eq!(
+            Err(FromBytesError::InvalidHeader),
+            BlockedBloomFilter::from_bytes(&[1u8, 0, 0, 0, 0, 100, 0, 0, 0, 7, 0, 0, 0]).map(|_| ())
+        );
+
+        let encoded = json::encode(&filter).unwrap();
+        let decoded: BlockedBloomFilter = json::decode(&encoded).unwrap();
+        assert_eq!(bytes, decoded.to_bytes());
+        assert!(json::decode::<BlockedBloomFilter>("[2]").is_err());
+    }
+
+    #[bench]
+    fn blocked_bloom_filter_contains(b: &mut Bencher) {
+        let mut filter = BlockedBloomFilter::with_rate(0.01, BENCH_LENGTH);
+        for i in 0 .. BENCH_LENGTH {
+            filter.push(&key(i));
+        }
+
+        let mut i = 0u32;
+        b.iter(|| {
+            i = i.wrapping_add(1);
+            black_box(filter.contains(&key(i.wrapping_mul(0x9e3779b9) % (2 * BENCH_LENGTH))))
+        })
+    }
+
+    #[bench]
+    fn bloom_filter_contains(b: &mut Bencher) {
+        let mut filter = BloomFilter::with_rate(0.01, BENCH_LENGTH);
+        for i in 0 .. BENCH_LENGTH {
+            filter.push(&key(i));
+        }
+
+        let mut i = 0u32;
+        b.iter(|| {
+            i = i.wrapping_add(1);
+            black_box(filter.contains(&key(i.wrapping_mul(0x9e3779b9) % (2 * BENCH_LENGTH))))
+        })
+    }
+}
@@ -1,11 +1,42 @@
 use bit_set::BitSet;
 use std::cmp::max;
-use algorithms::murmur::murmur3_32_seed;
+use std::fmt;
+use algorithms::hash::{HashFunction, Murmur3};
+use rustc_serialize::{Encodable, Encoder, Decodable, Decoder};
 
-pub struct BloomFilter {
+/// The version of the binary format produced by `BloomFilter::to_bytes()`.
+static FORMAT_VERSION: u8 = 1u8;
+
+/// The size of the binary header: a version byte followed by three little-endian `u32`s.
+static HEADER_LENGTH: usize = 13usize;
+
+/// An error that occurs when decoding a bloom filter from bytes.
+#[derive(Debug, PartialEq)]
+pub enum FromBytesError {
+    /// The format version is not supported by this implementation.
+    UnsupportedVersion(u8),
+    /// The input is too short or too long for the bloom filter it describes.
+    InvalidLength,
+    /// The header describes a bloom filter with no bits or no hash functions.
+    InvalidHeader,
+}
+
+impl fmt::Display for FromBytesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FromBytesError::UnsupportedVersion(version) => write!(f, "unsupported bloom filter format version {}", version),
+            FromBytesError::InvalidLength => write!(f, "invalid bloom filter length"),
+            FromBytesError::InvalidHeader => write!(f, "invalid bloom filter header"),
+        }
+    }
+}
+
+pub struct BloomFilter<H = Murmur3> {
     set: BitSet,
+    capacity: u32,
     expected_length: u32,
     hash_count: u32,
+    hash_function: H,
 }
 
 impl BloomFilter {
@@ -15,25 +46,73 @@ impl BloomFilter {
     /// expect to add. The latter is used to choose some optimal internal values to minimize the false-positive
     /// rate (which can be estimated with expected_false_positive_rate()).
     pub fn with_capacity(capacity: u32, expected_length: u32) -> BloomFilter {
+        BloomFilter::with_capacity_and_hasher(capacity, expected_length, Murmur3)
+    }
+
+    /// Constructs a new bloom filter that holds `expected_length` items at the given false-positive rate.
+    ///
+    /// The number of bits is derived from `m = -n * ln(p) / ln(2)^2`. Panics unless the rate is within `(0, 1)`.
+    pub fn with_rate(false_positive_rate: f32, expected_length: u32) -> BloomFilter {
+        BloomFilter::with_rate_and_hasher(false_positive_rate, expected_length, Murmur3)
+    }
+
+    /// Deserializes a bloom filter that was serialized with `to_bytes()`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<BloomFilter, FromBytesError> {
+        BloomFilter::from_bytes_and_hasher(bytes, Murmur3)
+    }
+}
+
+impl<H: HashFunction> BloomFilter<H> {
+    /// Constructs a new bloom filter that uses the given hash function.
+    pub fn with_capacity_and_hasher(capacity: u32, expected_length: u32, hash_function: H) -> BloomFilter<H> {
         let hash_count = (capacity / expected_length) as f32 * 2.0f32.ln();
 
         BloomFilter {
             set: BitSet::with_capacity(capacity as usize),
+            capacity: capacity,
             expected_length: expected_length,
             hash_count: max(1, hash_count.ceil() as u32),
+            hash_function: hash_function,
         }
     }
 
+    /// Constructs a new bloom filter for the given false-positive rate that uses the given hash function.
+    ///
+    /// The false-positive rate must be within `(0, 1)`.
+    pub fn with_rate_and_hasher(false_positive_rate: f32, expected_length: u32, hash_function: H) -> BloomFilter<H> {
+        // Written so that NaN is rejected as well.
+        if !(false_positive_rate > 0f32 && false_positive_rate < 1f32) {
+            panic!("the false-positive rate {} has to be within (0, 1)", false_positive_rate);
+        }
+
+        let ln2 = 2.0f32.ln();
+        let bits = -(expected_length as f32) * false_positive_rate.ln() / (ln2 * ln2);
+
+        BloomFilter::with_capacity_and_hasher(max(1, bits.ceil() as u32), max(1, expected_length), hash_function)
+    }
+
+    /// Returns the number of bits in the bloom filter.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Returns the number of items the bloom filter was sized for.
+    pub fn expected_length(&self) -> u32 {
+        self.expected_length
+    }
+
     /// Returns the expected false-positive rate.
     pub fn expected_false_positive_rate(&self) -> f32 {
         // (1 - e^(-k * n / m)) ^ k
-        let pre = (-(self.hash_count as f32) * self.expected_length as f32) / self.set.capacity() as f32;
+        let pre = (-(self.hash_count as f32) * self.expected_length as f32) / self.capacity as f32;
         (1f32 - pre.exp()).powi(self.hash_count as i32)
     }
 
     /// Pushes a new value to the bloom filter.
     pub fn push(&mut self, data: &[u8]) {
-        let hashes = (0 .. self.hash_count + 1).map(|i| murmur3_32_seed(data, i) as usize);
+        let capacity = self.capacity as u64;
+        let hash_function = &self.hash_function;
+        let hashes = (0 .. self.hash_count).map(|i| (hash_function.hash(data, i) % capacity) as usize);
 
         for hash in hashes {
             self.set.insert(hash);
@@ -47,10 +126,141 @@ impl BloomFilter {
 
     /// Returns false if the data was definitely not added to the bloom filter, and true if it may have been.
     pub fn contains(&self, data: &[u8]) -> bool {
-        let mut hashes = (0 .. self.hash_count + 1).map(|i| murmur3_32_seed(data, i) as usize);
+        let capacity = self.capacity as u64;
+        let mut hashes = (0 .. self.hash_count).map(|i| (self.hash_function.hash(data, i) % capacity) as usize);
 
         hashes.all(|h| self.set.contains(h))
     }
+
+    /// Returns whether the two bloom filters have identical parameters, and can thus be combined.
+    pub fn is_compatible(&self, other: &BloomFilter<H>) -> bool {
+        self.capacity == other.capacity &&
+        self.expected_length == other.expected_length &&
+        self.hash_count == other.hash_count
+    }
+
+    /// Adds every value of the other bloom filter to this one.
+    ///
+    /// The result is identical to a bloom filter that had all of the values pushed to it.
+    pub fn union(&mut self, other: &BloomFilter<H>) {
+        self.assert_compatible(other);
+        self.set.union_with(&other.set);
+    }
+
+    /// Keeps only the bits that are set in both bloom filters.
+    ///
+    /// The result contains every value that was pushed to both filters, but its false-positive rate may be
+    /// higher than that of a bloom filter that had only the common values pushed to it.
+    pub fn intersect(&mut self, other: &BloomFilter<H>) {
+        self.assert_compatible(other);
+        self.set.intersect_with(&other.set);
+    }
+
+    fn assert_compatible(&self, other: &BloomFilter<H>) {
+        if !self.is_compatible(other) {
+            panic!(
+                "incompatible bloom filters: ({}, {}, {}) and ({}, {}, {})",
+                self.capacity,
+                self.expected_length,
+                self.hash_count,
+                other.capacity,
+                other.expected_length,
+                other.hash_count
+            );
+        }
+    }
+
+    /// Serializes the bloom filter into a versioned binary format.
+    ///
+    /// The format is a version byte, followed by the capacity, the expected length and the hash count as
+    /// little-endian `u32`s, followed by the bits in ascending order with the least significant bit first.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + byte_length(self.capacity));
+
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&u32_to_bytes(self.capacity));
+        bytes.extend_from_slice(&u32_to_bytes(self.expected_length));
+        bytes.extend_from_slice(&u32_to_bytes(self.hash_count));
+        bytes.resize(HEADER_LENGTH + byte_length(self.capacity), 0u8);
+
+        for bit in self.set.iter() {
+            bytes[HEADER_LENGTH + bit / 8] |= 1u8 << (bit % 8);
+        }
+
+        bytes
+    }
+
+    /// Deserializes a bloom filter that was serialized with `to_bytes()`, and uses the given hash function.
+    ///
+    /// The hash function is not part of the binary format, so it has to be the one the bloom filter was built with.
+    pub fn from_bytes_and_hasher(bytes: &[u8], hash_function: H) -> Result<BloomFilter<H>, FromBytesError> {
+        if bytes.is_empty() {
+            return Err(FromBytesError::InvalidLength);
+        }
+
+        if bytes[0] != FORMAT_VERSION {
+            return Err(FromBytesError::UnsupportedVersion(bytes[0]));
+        }
+
+        if bytes.len() < HEADER_LENGTH {
+            return Err(FromBytesError::InvalidLength);
+        }
+
+        let capacity = u32_from_bytes(&bytes[1..]);
+        let expected_length = u32_from_bytes(&bytes[5..]);
+        let hash_count = u32_from_bytes(&bytes[9..]);
+
+        if capacity == 0 || hash_count == 0 {
+            return Err(FromBytesError::InvalidHeader);
+        }
+
+        let bits = &bytes[HEADER_LENGTH..];
+        if bits.len() != byte_length(capacity) {
+            return Err(FromBytesError::InvalidLength);
+        }
+
+        let mut set = BitSet::with_capacity(capacity as usize);
+        for bit in 0 .. capacity as usize {
+            if bits[bit / 8] & (1u8 << (bit % 8)) != 0 {
+                set.insert(bit);
+            }
+        }
+
+        Ok(BloomFilter {
+            set: set,
+            capacity: capacity,
+            expected_length: expected_length,
+            hash_count: hash_count,
+            hash_function: hash_function,
+        })
+    }
+}
+
+/// Returns the number of bytes that hold the given number of bits.
+fn byte_length(capacity: u32) -> usize {
+    (capacity as usize).div_ceil(8)
+}
+
+fn u32_to_bytes(value: u32) -> [u8; 4] {
+    [value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+}
+
+fn u32_from_bytes(data: &[u8]) -> u32 {
+    (data[0] as u32) | ((data[1] as u32) << 8) | ((data[2] as u32) << 16) | ((data[3] as u32) << 24)
+}
+
+/// Encodes the bloom filter as the byte sequence produced by `to_bytes()`.
+impl<H: HashFunction> Encodable for BloomFilter<H> {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        self.to_bytes().encode(s)
+    }
+}
+
+impl Decodable for BloomFilter {
+    fn decode<D: Decoder>(d: &mut D) -> Result<BloomFilter, D::Error> {
+        let bytes: Vec<u8> = Decodable::decode(d)?;
+        BloomFilter::from_bytes(&bytes).map_err(|e| d.error(&e.to_string()))
+    }
 }
 
 #[test]
@@ -69,6 +279,107 @@ fn false_positive() {
     assert_eq!(false, filter.contains(a.as_slice()));
     filter.push(a.as_slice());
     assert_eq!(true, filter.contains(a.as_slice()));
-    //assert_eq!(true, filter.contains([1u8].as_slice()));
-    // TODO: capacity will be increased! maybe MOD?
+    // The positions wrap around the single bit, so every value is a false positive.
+    assert_eq!(true, filter.contains(&[1u8]));
+}
+
+#[test]
+fn probes_hash_count_bits() {
+    // 10 bits per value take 7 hash functions, and a value sets exactly as many bits.
+    let mut filter = BloomFilter::with_capacity(1000, 100);
+    filter.push(&[1u8, 2, 3, 4]);
+    assert_eq!(7, filter.hash_count);
+    assert_eq!(7, filter.set.len());
+}
+
+#[test]
+fn with_rate() {
+    let filter = BloomFilter::with_rate(0.01, 1000);
+    assert_eq!(9586, filter.capacity());
+    assert!(filter.expected_false_positive_rate() < 0.011);
+}
+
+#[test]
+fn with_rate_out_of_range() {
+    use std::panic;
+
+    for &rate in [0.0, 1.0, -0.01, 1.5, f32::NAN].iter() {
+        assert!(panic::catch_unwind(|| BloomFilter::with_rate(rate, 1000)).is_err(), "rate {}", rate);
+    }
+}
+
+#[test]
+fn union_and_intersect() {
+    let mut a = BloomFilter::with_rate(0.01, 100);
+    let mut b = BloomFilter::with_rate(0.01, 100);
+    assert!(a.is_compatible(&b));
+    assert!(!a.is_compatible(&BloomFilter::with_rate(0.01, 200)));
+
+    a.push(&[1u8]);
+    a.push(&[2u8]);
+    b.push(&[2u8]);
+    b.push(&[3u8]);
+
+    let mut union = BloomFilter::from_bytes(&a.to_bytes()).unwrap();
+    union.union(&b);
+    assert!(union.contains(&[1u8]) && union.contains(&[2u8]) && union.contains(&[3u8]));
+
+    a.intersect(&b);
+    assert!(a.contains(&[2u8]));
+    assert!(!a.contains(&[1u8]) && !a.contains(&[3u8]));
+}
+
+#[test]
+#[should_panic]
+fn union_incompatible() {
+    let mut a = BloomFilter::with_capacity(100, 10);
+    a.union(&BloomFilter::with_capacity(200, 10));
+}
+
+#[test]
+fn serialization() {
+    use rustc_serialize::json;
+
+    let mut filter = BloomFilter::with_capacity(20, 2);
+    filter.push(&[1u8, 2, 3, 4]);
+
+    let bytes = filter.to_bytes();
+    assert_eq!([1u8, 20, 0, 0, 0, 2, 0, 0, 0, 7, 0, 0, 0], &bytes[..13]);
+    assert_eq!(16, bytes.len());
+
+    let decoded = BloomFilter::from_bytes(&bytes).unwrap();
+    assert!(decoded.is_compatible(&filter));
+    assert!(decoded.contains(&[1u8, 2, 3, 4]));
+    assert_eq!(bytes, decoded.to_bytes());
+
+    assert_eq!(Err(FromBytesError::UnsupportedVersion(2)), BloomFilter::from_bytes(&[2u8]).map(|_| ()));
+    assert_eq!(Err(FromBytesError::InvalidLength), BloomFilter::from_bytes(&bytes[..15]).map(|_| ()));
+
+    let encoded = json::encode(&filter).unwrap();
+    let decoded: BloomFilter = json::decode(&encoded).unwrap();
+    assert_eq!(bytes, decoded.to_bytes());
+    assert!(json::decode::<BloomFilter>("[2]").is_err());
+}
+
+#[test]
+fn hash_functions() {
+    use algorithms::hash::{Fnv1a, XxHash64};
+
+    let mut fnv = BloomFilter::with_rate_and_hasher(0.01, 100, Fnv1a);
+    let mut xxhash = BloomFilter::with_rate_and_hasher(0.01, 100, XxHash64);
+
+    for i in 0 .. 100u8 {
+        fnv.push(&[i]);
+        xxhash.push(&[i]);
+    }
+
+    for i in 0 .. 100u8 {
+        assert!(fnv.contains(&[i]));
+        assert!(xxhash.contains(&[i]));
+    }
+
+    assert!(fnv.to_bytes() != xxhash.to_bytes());
+
+    let decoded = BloomFilter::from_bytes_and_hasher(&fnv.to_bytes(), Fnv1a).unwrap();
+    assert!(decoded.contains(&[1u8]));
 }
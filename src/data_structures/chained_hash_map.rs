@@ -0,0 +1,390 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::iter::Chain;
+use std::mem;
+use std::slice;
+use data_structures::doubly_linked_list::{self, DoublyLinkedList};
+use algorithms::murmur::BuildMurmur3;
+
+static MIN_BUCKETS: usize = 16usize;
+static DEFAULT_MAX_LOAD_FACTOR: f32 = 1.0f32;
+
+/// The number of old buckets moved to the new table by every insertion or removal during a rehash.
+static REHASH_STEP: usize = 4usize;
+
+/// An iterator over the buckets of a table.
+type Buckets<'a, K, V> = slice::Iter<'a, DoublyLinkedList<Entry<K, V>>>;
+
+struct Entry<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+/// An implementation of a hash map with separate chaining.
+///
+/// Every bucket is a `DoublyLinkedList` of the entries whose hashes map to it. The number of buckets doubles when
+/// the load factor, the number of entries per bucket, would exceed the maximum load factor.
+///
+/// The map rehashes incrementally rather than stopping the world: when it grows, the old buckets are kept next to
+/// the new ones, and every following insertion or removal moves a few of the old buckets to the new table. Lookups
+/// search both tables until the rehash completes. This bounds the cost of every single operation, at the cost of
+/// temporarily using memory for both tables.
+///
+/// The iteration order is arbitrary. It does not change as long as the map is not modified, but any insertion or
+/// removal may change it, because it may move buckets to the new table.
+pub struct ChainedHashMap<K, V, S = BuildMurmur3> {
+    buckets: Vec<DoublyLinkedList<Entry<K, V>>>,
+    old_buckets: Vec<DoublyLinkedList<Entry<K, V>>>,
+    rehashed: usize,
+    length: usize,
+    max_load_factor: f32,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq, V> ChainedHashMap<K, V, BuildMurmur3> {
+    /// Creates a new empty hash map with a maximum load factor of 1.
+    pub fn new() -> ChainedHashMap<K, V, BuildMurmur3> {
+        ChainedHashMap::with_max_load_factor(DEFAULT_MAX_LOAD_FACTOR)
+    }
+
+    /// Creates a new empty hash map with the given maximum load factor.
+    pub fn with_max_load_factor(max_load_factor: f32) -> ChainedHashMap<K, V, BuildMurmur3> {
+        ChainedHashMap::with_max_load_factor_and_hasher(max_load_factor, BuildMurmur3::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ChainedHashMap<K, V, S> {
+    /// Creates a new empty hash map with the given maximum load factor, which uses the given hash builder to hash the
+    /// keys.
+    ///
+    /// The maximum load factor has to be at least `0.25`, so that a rehash always completes before the next one is
+    /// due. Higher load factors use less memory, but make the chains longer.
+    pub fn with_max_load_factor_and_hasher(max_load_factor: f32, hash_builder: S) -> ChainedHashMap<K, V, S> {
+        if max_load_factor.is_nan() || max_load_factor < 0.25 {
+            panic!("the maximum load factor {} has to be at least 0.25", max_load_factor);
+        }
+
+        ChainedHashMap {
+            buckets: ChainedHashMap::<K, V, S>::empty_buckets(MIN_BUCKETS),
+            old_buckets: Vec::new(),
+            rehashed: 0,
+            length: 0,
+            max_load_factor: max_load_factor,
+            hash_builder: hash_builder,
+        }
+    }
+
+    fn empty_buckets(count: usize) -> Vec<DoublyLinkedList<Entry<K, V>>> {
+        (0 .. count).map(|_| DoublyLinkedList::new()).collect()
+    }
+
+    fn make_hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        let mut hasher = self.hash_builder.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the table, the bucket and the position within the bucket of the key.
+    ///
+    /// The table is `false` for the new buckets and `true` for the old ones. Only the old buckets that have not
+    /// been moved yet are searched.
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<(bool, usize, usize)> where K: Borrow<Q>, Q: Eq + ?Sized {
+        let bucket = hash as usize & (self.buckets.len() - 1);
+        if let Some(position) = self.buckets[bucket].iter().position(|entry| entry.hash == hash && entry.key.borrow() == key) {
+            return Some((false, bucket, position));
+        }
+
+        if self.is_rehashing() {
+            let bucket = hash as usize & (self.old_buckets.len() - 1);
+            if bucket >= self.rehashed {
+                let old = &self.old_buckets[bucket];
+                if let Some(position) = old.iter().position(|entry| entry.hash == hash && entry.key.borrow() == key) {
+                    return Some((true, bucket, position));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn bucket_mut(&mut self, old: bool, bucket: usize) -> &mut DoublyLinkedList<Entry<K, V>> {
+        if old {
+            &mut self.old_buckets[bucket]
+        } else {
+            &mut self.buckets[bucket]
+        }
+    }
+
+    /// Moves up to `steps` old buckets to the new table, and ends the rehash when all of them have been moved.
+    fn rehash_step(&mut self, steps: usize) {
+        let mask = self.buckets.len() - 1;
+        let end = self.old_buckets.len().min(self.rehashed.saturating_add(steps));
+
+        for bucket in self.rehashed .. end {
+            while let Some(entry) = self.old_buckets[bucket].pop_front() {
+                self.buckets[entry.hash as usize & mask].push_back(entry);
+            }
+        }
+
+        self.rehashed = end;
+        if self.rehashed == self.old_buckets.len() {
+            self.old_buckets = Vec::new();
+            self.rehashed = 0;
+        }
+    }
+
+    /// Starts a rehash into twice as many buckets if one more entry would exceed the maximum load factor.
+    fn grow_if_needed(&mut self) {
+        if (self.length + 1) as f32 <= self.buckets.len() as f32 * self.max_load_factor {
+            return;
+        }
+
+        // A rehash that is still running has to complete first, so that there are never more than two tables.
+        if self.is_rehashing() {
+            self.rehash_step(usize::MAX);
+        }
+
+        let buckets = ChainedHashMap::<K, V, S>::empty_buckets(self.buckets.len() * 2);
+        self.old_buckets = mem::replace(&mut self.buckets, buckets);
+        self.rehashed = 0;
+    }
+
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map already contained the key, the value is replaced and the old value is returned. The key is not
+    /// replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.is_rehashing() {
+            self.rehash_step(REHASH_STEP);
+        }
+
+        let hash = self.make_hash(&key);
+        if let Some((old, bucket, position)) = self.find(hash, &key) {
+            let entry = self.bucket_mut(old, bucket).iter_mut().nth(position).unwrap();
+            return Some(mem::replace(&mut entry.value, value));
+        }
+
+        self.grow_if_needed();
+
+        let bucket = hash as usize & (self.buckets.len() - 1);
+        self.buckets[bucket].push_back(Entry { hash: hash, key: key, value: value });
+        self.length += 1;
+        None
+    }
+
+    /// Returns a reference to the value of the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V> where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.make_hash(key);
+        self.find(hash, key).map(|(old, bucket, position)| {
+            let buckets = if old { &self.old_buckets } else { &self.buckets };
+            &buckets[bucket].iter().nth(position).unwrap().value
+        })
+    }
+
+    /// Returns a mutable reference to the value of the key.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.make_hash(key);
+        match self.find(hash, key) {
+            Some((old, bucket, position)) => Some(&mut self.bucket_mut(old, bucket).iter_mut().nth(position).unwrap().value),
+            None => None,
+        }
+    }
+
+    /// Returns true if the map contains the key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.make_hash(key);
+        self.find(hash, key).is_some()
+    }
+
+    /// Removes the key from the map, and returns its value if the map contained it.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        if self.is_rehashing() {
+            self.rehash_step(REHASH_STEP);
+        }
+
+        let hash = self.make_hash(key);
+        self.find(hash, key).map(|(old, bucket, position)| {
+            self.length -= 1;
+            self.bucket_mut(old, bucket).remove_at(position).value
+        })
+    }
+}
+
+impl<K, V, S> ChainedHashMap<K, V, S> {
+    /// Returns an iterator over the entries, in an arbitrary order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            buckets: self.old_buckets[self.rehashed ..].iter().chain(self.buckets.iter()),
+            bucket: None,
+            remaining: self.length,
+        }
+    }
+
+    /// Removes all entries, and ends a running rehash. The number of buckets is kept.
+    pub fn clear(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            bucket.clear();
+        }
+
+        self.old_buckets = Vec::new();
+        self.rehashed = 0;
+        self.length = 0;
+    }
+
+    /// Returns the number of entries.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the number of buckets of the current table.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the number of entries per bucket of the current table.
+    pub fn load_factor(&self) -> f32 {
+        self.length as f32 / self.buckets.len() as f32
+    }
+
+    /// Returns true while old buckets are being moved to the new table.
+    pub fn is_rehashing(&self) -> bool {
+        !self.old_buckets.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V> Default for ChainedHashMap<K, V, BuildMurmur3> {
+    fn default() -> ChainedHashMap<K, V, BuildMurmur3> {
+        ChainedHashMap::new()
+    }
+}
+
+/// An iterator over the entries of a `ChainedHashMap`.
+pub struct Iter<'a, K: 'a, V: 'a> {
+    buckets: Chain<Buckets<'a, K, V>, Buckets<'a, K, V>>,
+    bucket: Option<doubly_linked_list::Iter<'a, Entry<K, V>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Some(entry) = self.bucket.as_mut().and_then(|bucket| bucket.next()) {
+                self.remaining -= 1;
+                return Some((&entry.key, &entry.value));
+            }
+
+            self.bucket = Some(self.buckets.next()?.iter());
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a ChainedHashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections;
+
+    #[test]
+    fn basic_tests() {
+        let mut map = ChainedHashMap::new();
+        assert!(map.is_empty());
+        assert_eq!(None, map.get("a"));
+
+        assert_eq!(None, map.insert("a".to_string(), 1));
+        assert_eq!(None, map.insert("b".to_string(), 2));
+        assert_eq!(Some(1), map.insert("a".to_string(), 3));
+        assert_eq!(2, map.length());
+        assert_eq!(Some(&3), map.get("a"));
+        assert!(map.contains_key("b"));
+
+        *map.get_mut("b").unwrap() += 10;
+        assert_eq!(Some(&12), map.get("b"));
+
+        assert_eq!(Some(3), map.remove("a"));
+        assert_eq!(None, map.remove("a"));
+        assert_eq!(1, map.length());
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(None, map.get("b"));
+    }
+
+    /// Compares the map to std's map over a mix of insertions and removals, checking every key while rehashes are
+    /// still running.
+    #[test]
+    fn matches_std_hash_map() {
+        let mut map = ChainedHashMap::with_max_load_factor(0.75);
+        let mut expected = collections::HashMap::new();
+        let mut was_rehashing = false;
+
+        for i in 0 .. 20_000u32 {
+            let key = i.wrapping_mul(2654435761) % 5_000;
+            if i % 3 == 0 {
+                assert_eq!(expected.remove(&key), map.remove(&key));
+            } else {
+                assert_eq!(expected.insert(key, i), map.insert(key, i));
+            }
+
+            assert_eq!(expected.len(), map.length());
+            assert!(map.load_factor() <= 0.75);
+
+            if map.is_rehashing() && !was_rehashing {
+                for (key, value) in expected.iter() {
+                    assert_eq!(Some(value), map.get(key));
+                }
+                assert_eq!(expected.len(), map.iter().count());
+            }
+            was_rehashing = map.is_rehashing();
+        }
+
+        let mut entries: Vec<(u32, u32)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        let mut expected_entries: Vec<(u32, u32)> = expected.iter().map(|(&k, &v)| (k, v)).collect();
+        entries.sort();
+        expected_entries.sort();
+        assert_eq!(expected_entries, entries);
+    }
+
+    #[test]
+    fn rehashes_incrementally() {
+        let mut map = ChainedHashMap::new();
+        for i in 0 .. 16 {
+            map.insert(i, i);
+        }
+        assert_eq!(16, map.bucket_count());
+        assert!(!map.is_rehashing());
+
+        // The 17th entry starts the rehash, and every insertion moves four of the 16 old buckets.
+        map.insert(16, 16);
+        assert_eq!(32, map.bucket_count());
+        assert!(map.is_rehashing());
+
+        for i in 17 .. 20 {
+            map.insert(i, i);
+            assert!(map.is_rehashing());
+        }
+
+        map.insert(20, 20);
+        assert!(!map.is_rehashing());
+        assert!((0 .. 21).all(|i| map.get(&i) == Some(&i)));
+    }
+}
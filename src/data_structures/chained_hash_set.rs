@@ -0,0 +1,127 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use data_structures::chained_hash_map::{self, ChainedHashMap};
+use algorithms::murmur::BuildMurmur3;
+
+/// An implementation of a hash set with separate chaining, which is a `ChainedHashMap` without values.
+///
+/// The same incremental rehashing and iteration order guarantees apply.
+pub struct ChainedHashSet<A, S = BuildMurmur3> {
+    map: ChainedHashMap<A, (), S>,
+}
+
+impl<A: Hash + Eq> ChainedHashSet<A, BuildMurmur3> {
+    /// Creates a new empty hash set with a maximum load factor of 1.
+    pub fn new() -> ChainedHashSet<A, BuildMurmur3> {
+        ChainedHashSet { map: ChainedHashMap::new() }
+    }
+
+    /// Creates a new empty hash set with the given maximum load factor.
+    pub fn with_max_load_factor(max_load_factor: f32) -> ChainedHashSet<A, BuildMurmur3> {
+        ChainedHashSet { map: ChainedHashMap::with_max_load_factor(max_load_factor) }
+    }
+}
+
+impl<A: Hash + Eq, S: BuildHasher> ChainedHashSet<A, S> {
+    /// Creates a new empty hash set with the given maximum load factor, which uses the given hash builder.
+    pub fn with_max_load_factor_and_hasher(max_load_factor: f32, hash_builder: S) -> ChainedHashSet<A, S> {
+        ChainedHashSet { map: ChainedHashMap::with_max_load_factor_and_hasher(max_load_factor, hash_builder) }
+    }
+
+    /// Adds the value to the set. Returns false if the set already contained it.
+    pub fn insert(&mut self, value: A) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    /// Returns true if the set contains the value.
+    pub fn contains<Q>(&self, value: &Q) -> bool where A: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.map.contains_key(value)
+    }
+
+    /// Removes the value from the set. Returns false if the set did not contain it.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool where A: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        self.map.remove(value).is_some()
+    }
+}
+
+impl<A, S> ChainedHashSet<A, S> {
+    /// Returns an iterator over the values, in an arbitrary order.
+    pub fn iter(&self) -> Iter<'_, A> {
+        Iter { iter: self.map.iter() }
+    }
+
+    /// Removes all values.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Returns the number of values.
+    pub fn length(&self) -> usize {
+        self.map.length()
+    }
+
+    /// Returns true if the set has no values.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the number of values per bucket.
+    pub fn load_factor(&self) -> f32 {
+        self.map.load_factor()
+    }
+}
+
+impl<A: Hash + Eq> Default for ChainedHashSet<A, BuildMurmur3> {
+    fn default() -> ChainedHashSet<A, BuildMurmur3> {
+        ChainedHashSet::new()
+    }
+}
+
+/// An iterator over the values of a `ChainedHashSet`.
+pub struct Iter<'a, A: 'a> {
+    iter: chained_hash_map::Iter<'a, A, ()>,
+}
+
+impl<'a, A> Iterator for Iter<'a, A> {
+    type Item = &'a A;
+
+    fn next(&mut self) -> Option<&'a A> {
+        self.iter.next().map(|(value, _)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, A, S> IntoIterator for &'a ChainedHashSet<A, S> {
+    type Item = &'a A;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Iter<'a, A> {
+        self.iter()
+    }
+}
+
+#[test]
+fn basic_tests() {
+    let mut set = ChainedHashSet::new();
+    assert!(set.is_empty());
+
+    for i in 0 .. 1_000 {
+        assert_eq!(i < 500, set.insert(i % 500));
+    }
+    assert_eq!(500, set.length());
+    assert!(set.contains(&250));
+    assert!(!set.contains(&500));
+
+    assert!(set.remove(&250));
+    assert!(!set.remove(&250));
+    assert!(!set.contains(&250));
+
+    let mut values: Vec<u32> = set.iter().cloned().collect();
+    values.sort();
+    assert_eq!(499, values.len());
+    assert_eq!(249, values[249]);
+    assert_eq!(251, values[250]);
+}
@@ -0,0 +1,291 @@
+use std::cmp::{max, min, Ordering};
+use std::collections::HashMap;
+use algorithms::hash::{HashFunction, Murmur3};
+use data_structures::binary_heap::BinaryHeap;
+
+/// An implementation of a Count-Min sketch (Cormode and Muthukrishnan, 2005).
+///
+/// A Count-Min sketch estimates how many times each value has been counted, using a fixed amount of memory.
+/// Every value is hashed to one counter in each of `depth` rows of `width` counters. The estimate is the
+/// minimum of those counters, which is never less than the true count.
+///
+/// With `width = ceil(e / epsilon)` and `depth = ceil(ln(1 / delta))`, the estimate exceeds the true count by at
+/// most `epsilon * N` with a probability of `1 - delta`, where `N` is the total of all counts.
+pub struct CountMinSketch<H = Murmur3> {
+    counters: Vec<u64>,
+    width: usize,
+    depth: usize,
+    hash_function: H,
+}
+
+impl CountMinSketch {
+    /// Constructs a new Count-Min sketch for the given error bound and failure probability.
+    pub fn new(epsilon: f64, delta: f64) -> CountMinSketch {
+        CountMinSketch::with_hasher(epsilon, delta, Murmur3)
+    }
+
+    /// Constructs a new Count-Min sketch with `depth` rows of `width` counters.
+    pub fn with_dimensions(width: usize, depth: usize) -> CountMinSketch {
+        CountMinSketch::with_dimensions_and_hasher(width, depth, Murmur3)
+    }
+}
+
+impl<H: HashFunction> CountMinSketch<H> {
+    /// Constructs a new Count-Min sketch for the given error bound and failure probability, that uses the given
+    /// hash function.
+    pub fn with_hasher(epsilon: f64, delta: f64, hash_function: H) -> CountMinSketch<H> {
+        let width = (1f64.exp() / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil() as usize;
+
+        CountMinSketch::with_dimensions_and_hasher(max(1, width), max(1, depth), hash_function)
+    }
+
+    /// Constructs a new Count-Min sketch with `depth` rows of `width` counters, that uses the given hash function.
+    pub fn with_dimensions_and_hasher(width: usize, depth: usize, hash_function: H) -> CountMinSketch<H> {
+        CountMinSketch {
+            counters: vec![0u64; width * depth],
+            width: width,
+            depth: depth,
+            hash_function: hash_function,
+        }
+    }
+
+    #[inline]
+    fn index(&self, data: &[u8], row: usize) -> usize {
+        row * self.width + (self.hash_function.hash(data, row as u32) % self.width as u64) as usize
+    }
+
+    /// Adds `count` to the counters of the value.
+    pub fn increment(&mut self, data: &[u8], count: u64) {
+        for row in 0 .. self.depth {
+            let index = self.index(data, row);
+            self.counters[index] = self.counters[index].saturating_add(count);
+        }
+    }
+
+    /// Adds `count` to the value using conservative update.
+    ///
+    /// Only the counters that would otherwise end up below the new estimate are raised. This keeps the estimates
+    /// of the other values sharing those counters lower, while the estimates still never undercount.
+    pub fn increment_conservative(&mut self, data: &[u8], count: u64) {
+        let estimate = self.estimate(data).saturating_add(count);
+
+        for row in 0 .. self.depth {
+            let index = self.index(data, row);
+            self.counters[index] = max(self.counters[index], estimate);
+        }
+    }
+
+    /// Returns the estimated count of the value. It is never less than the true count.
+    pub fn estimate(&self, data: &[u8]) -> u64 {
+        (0 .. self.depth).fold(u64::MAX, |acc, row| min(acc, self.counters[self.index(data, row)]))
+    }
+
+    /// Adds the counts of the other sketch to this one. Both must have the same dimensions.
+    pub fn merge(&mut self, other: &CountMinSketch<H>) {
+        if self.width != other.width || self.depth != other.depth {
+            panic!(
+                "cannot merge Count-Min sketches of {}x{} and {}x{} counters",
+                self.depth,
+                self.width,
+                other.depth,
+                other.width
+            );
+        }
+
+        for (counter, &other_counter) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *counter = counter.saturating_add(other_counter);
+        }
+    }
+
+    /// Clears the sketch.
+    pub fn clear(&mut self) {
+        for counter in self.counters.iter_mut() {
+            *counter = 0;
+        }
+    }
+
+    /// Returns the number of counters per row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// A value in the heavy hitters heap. The ordering is reversed, so that the max-heap pops the least frequent value.
+#[derive(PartialEq, Eq)]
+struct Candidate {
+    count: u64,
+    data: Vec<u8>,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other.count.cmp(&self.count).then_with(|| other.data.cmp(&self.data))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Tracks the most frequent values in a stream.
+///
+/// The counts are estimated with a Count-Min sketch, and the current top values are kept in a heap of at most `k`
+/// values. When a value is estimated to be more frequent than the least frequent top value, it takes its place.
+pub struct HeavyHitters {
+    sketch: CountMinSketch,
+    k: usize,
+    top: HashMap<Vec<u8>, u64>,
+    heap: BinaryHeap<Candidate>,
+}
+
+impl HeavyHitters {
+    /// Constructs a new tracker of the `k` most frequent values, whose counts are estimated within `epsilon * N`
+    /// with a probability of `1 - delta`.
+    pub fn new(k: usize, epsilon: f64, delta: f64) -> HeavyHitters {
+        HeavyHitters {
+            sketch: CountMinSketch::new(epsilon, delta),
+            k: k,
+            top: HashMap::new(),
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Counts an occurrence of the value.
+    pub fn increment(&mut self, data: &[u8]) {
+        self.sketch.increment_conservative(data, 1);
+        let estimate = self.sketch.estimate(data);
+
+        if let Some(count) = self.top.get_mut(data) {
+            // The heap entry is refreshed lazily, when it reaches the top of the heap.
+            *count = estimate;
+            return;
+        }
+
+        if self.top.len() < self.k {
+            self.insert(data, estimate);
+            return;
+        }
+
+        // Refresh stale heap entries until the least frequent value is at the top of the heap.
+        while let Some(candidate) = self.heap.pop() {
+            let count = self.top[&candidate.data];
+
+            if count == candidate.count {
+                if estimate > count {
+                    self.top.remove(&candidate.data);
+                    self.insert(data, estimate);
+                } else {
+                    self.heap.push(candidate);
+                }
+
+                return;
+            }
+
+            self.heap.push(Candidate { count: count, data: candidate.data });
+        }
+    }
+
+    fn insert(&mut self, data: &[u8], count: u64) {
+        self.top.insert(data.to_vec(), count);
+        self.heap.push(Candidate { count: count, data: data.to_vec() });
+    }
+
+    /// Returns the estimated count of the value.
+    pub fn estimate(&self, data: &[u8]) -> u64 {
+        self.sketch.estimate(data)
+    }
+
+    /// Returns the most frequent values and their estimated counts, the most frequent first.
+    pub fn top(&self) -> Vec<(Vec<u8>, u64)> {
+        let mut top: Vec<(Vec<u8>, u64)> = self.top.iter().map(|(data, &count)| (data.clone(), count)).collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::key;
+
+    #[test]
+    fn basic_tests() {
+        let mut sketch = CountMinSketch::new(0.01, 0.01);
+        assert_eq!(272, sketch.width());
+        assert_eq!(5, sketch.depth());
+
+        assert_eq!(0, sketch.estimate(&[1u8]));
+        sketch.increment(&[1u8], 3);
+        sketch.increment(&[1u8], 2);
+        assert_eq!(5, sketch.estimate(&[1u8]));
+
+        sketch.clear();
+        assert_eq!(0, sketch.estimate(&[1u8]));
+    }
+
+    #[test]
+    fn error_bounds() {
+        let mut sketch = CountMinSketch::new(0.001, 0.01);
+        let mut conservative = CountMinSketch::new(0.001, 0.01);
+        let mut total = 0;
+
+        for i in 0 .. 5_000 {
+            sketch.increment(&key(i), (i % 10) as u64);
+            conservative.increment_conservative(&key(i), (i % 10) as u64);
+            total += i % 10;
+        }
+
+        for i in 0 .. 5_000 {
+            let estimate = sketch.estimate(&key(i));
+            assert!(estimate >= (i % 10) as u64);
+            assert!(estimate - ((i % 10) as u64) <= (0.001 * total as f64) as u64);
+
+            let conservative_estimate = conservative.estimate(&key(i));
+            assert!(conservative_estimate >= (i % 10) as u64);
+            assert!(conservative_estimate <= estimate);
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = CountMinSketch::with_dimensions(100, 4);
+        let mut b = CountMinSketch::with_dimensions(100, 4);
+
+        a.increment(&[1u8], 5);
+        b.increment(&[1u8], 7);
+        b.increment(&[2u8], 1);
+
+        a.merge(&b);
+        assert_eq!(12, a.estimate(&[1u8]));
+        assert_eq!(1, a.estimate(&[2u8]));
+    }
+
+    #[test]
+    fn heavy_hitters() {
+        let mut heavy_hitters = HeavyHitters::new(3, 0.001, 0.01);
+
+        // Values 0, 1 and 2 occur 300, 200 and 100 times, among 2000 values that occur once.
+        for i in 0 .. 2_000 {
+            heavy_hitters.increment(&key(1_000 + i));
+
+            if i < 300 { heavy_hitters.increment(&key(0)); }
+            if i < 200 { heavy_hitters.increment(&key(1)); }
+            if i < 100 { heavy_hitters.increment(&key(2)); }
+        }
+
+        let top = heavy_hitters.top();
+        assert_eq!(3, top.len());
+        assert_eq!(key(0).to_vec(), top[0].0);
+        assert_eq!(key(1).to_vec(), top[1].0);
+        assert_eq!(key(2).to_vec(), top[2].0);
+        assert!(top[0].1 >= 300);
+    }
+}
@@ -0,0 +1,305 @@
+use std::mem;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use algorithms::hash::{HashFunction, Murmur3};
+
+/// The number of fingerprints stored in each bucket.
+const BUCKET_SIZE: usize = 4usize;
+
+/// The maximum number of times a fingerprint is kicked out of its bucket before giving up.
+static MAX_KICKS: usize = 500usize;
+
+/// An empty slot in a bucket. Fingerprints are never zero.
+static EMPTY: u8 = 0u8;
+
+/// The seed of the generator that picks the fingerprints to kick out, so that the filter is reproducible.
+static KICK_SEED: [u32; 4] = [0x2f6b3a1d, 0x94c0e857, 0x5d12a9f3, 0xc7e4b062];
+
+/// An error that occurs when inserting into a cuckoo filter.
+#[derive(Debug, PartialEq)]
+pub enum CuckooFilterError {
+    /// The filter has no room for the value. The value was not added.
+    Full,
+}
+
+/// An implementation of a cuckoo filter (Fan et al., 2014).
+///
+/// Like a bloom filter, a cuckoo filter answers whether a value may have been added, but it also supports
+/// deleting values. It stores an 8-bit fingerprint of each value in one of two candidate buckets, and makes
+/// room by kicking existing fingerprints to their alternative bucket (partial-key cuckoo hashing).
+///
+/// The false-positive rate is roughly `2 * 4 / 2^8`, about 3%, and the filter works well up to a load
+/// factor of about 95%.
+///
+/// The fingerprint and the buckets are taken from the lower 32 bits of the hash.
+pub struct CuckooFilter<H = Murmur3> {
+    buckets: Vec<[u8; BUCKET_SIZE]>,
+    length: usize,
+    rng: XorShiftRng,
+    hash_function: H,
+}
+
+impl CuckooFilter {
+    /// Constructs a new cuckoo filter that has room for at least `capacity` values.
+    ///
+    /// The number of buckets is rounded up to a power of two.
+    pub fn with_capacity(capacity: usize) -> CuckooFilter {
+        CuckooFilter::with_capacity_and_hasher(capacity, Murmur3)
+    }
+}
+
+impl<H: HashFunction> CuckooFilter<H> {
+    /// Constructs a new cuckoo filter that has room for at least `capacity` values, and uses the given hash
+    /// function.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_function: H) -> CuckooFilter<H> {
+        let bucket_count = capacity.div_ceil(BUCKET_SIZE).next_power_of_two();
+
+        CuckooFilter {
+            buckets: vec![[EMPTY; BUCKET_SIZE]; bucket_count],
+            length: 0,
+            rng: XorShiftRng::from_seed(KICK_SEED),
+            hash_function: hash_function,
+        }
+    }
+
+    /// Returns the fingerprint and the first bucket index of the value.
+    fn fingerprint_and_index(&self, data: &[u8]) -> (u8, usize) {
+        let hash = self.hash_function.hash(data, 0) as u32;
+        let fingerprint = if (hash >> 24) as u8 == EMPTY { 1u8 } else { (hash >> 24) as u8 };
+
+        (fingerprint, hash as usize & (self.buckets.len() - 1))
+    }
+
+    /// Returns the other bucket of a fingerprint. This is an involution: `alt(alt(i, f), f) == i`.
+    fn alternate_index(&self, index: usize, fingerprint: u8) -> usize {
+        (index ^ self.hash_function.hash(&[fingerprint], 1) as usize) & (self.buckets.len() - 1)
+    }
+
+    fn insert_into_bucket(&mut self, index: usize, fingerprint: u8) -> bool {
+        match self.buckets[index].iter().position(|&slot| slot == EMPTY) {
+            Some(slot) => {
+                self.buckets[index][slot] = fingerprint;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn remove_from_bucket(&mut self, index: usize, fingerprint: u8) -> bool {
+        match self.buckets[index].iter().position(|&slot| slot == fingerprint) {
+            Some(slot) => {
+                self.buckets[index][slot] = EMPTY;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Inserts a new value to the cuckoo filter.
+    ///
+    /// Returns `CuckooFilterError::Full` if no room could be made for the value within a bounded number of
+    /// kicks. The filter is left unchanged then.
+    pub fn insert(&mut self, data: &[u8]) -> Result<(), CuckooFilterError> {
+        let (fingerprint, first_index) = self.fingerprint_and_index(data);
+        let second_index = self.alternate_index(first_index, fingerprint);
+
+        if self.insert_into_bucket(first_index, fingerprint) || self.insert_into_bucket(second_index, fingerprint) {
+            self.length += 1;
+            return Ok(());
+        }
+
+        let mut index = if self.rng.gen() { first_index } else { second_index };
+        let mut fingerprint = fingerprint;
+        let mut kicks = Vec::with_capacity(MAX_KICKS);
+
+        for _ in 0 .. MAX_KICKS {
+            let slot = self.rng.gen_range(0, BUCKET_SIZE);
+            mem::swap(&mut fingerprint, &mut self.buckets[index][slot]);
+            kicks.push((index, slot));
+
+            index = self.alternate_index(index, fingerprint);
+            if self.insert_into_bucket(index, fingerprint) {
+                self.length += 1;
+                return Ok(());
+            }
+        }
+
+        // The last kicked out fingerprint has no bucket, so the kicks are undone in reverse order, which puts every
+        // fingerprint back in its slot and leaves the value's own fingerprint in hand.
+        for &(index, slot) in kicks.iter().rev() {
+            mem::swap(&mut fingerprint, &mut self.buckets[index][slot]);
+        }
+
+        Err(CuckooFilterError::Full)
+    }
+
+    /// Returns false if the data was definitely not added to the cuckoo filter, and true if it may have been.
+    pub fn contains(&self, data: &[u8]) -> bool {
+        let (fingerprint, first_index) = self.fingerprint_and_index(data);
+        let second_index = self.alternate_index(first_index, fingerprint);
+
+        self.buckets[first_index].contains(&fingerprint) ||
+        self.buckets[second_index].contains(&fingerprint)
+    }
+
+    /// Deletes a value from the cuckoo filter. Returns whether the value (possibly) was in the filter.
+    ///
+    /// Only values that were definitely added should be deleted, otherwise another value that shares the
+    /// fingerprint may be deleted instead.
+    pub fn delete(&mut self, data: &[u8]) -> bool {
+        let (fingerprint, first_index) = self.fingerprint_and_index(data);
+        let second_index = self.alternate_index(first_index, fingerprint);
+
+        if !self.remove_from_bucket(first_index, fingerprint) && !self.remove_from_bucket(second_index, fingerprint) {
+            return false;
+        }
+
+        self.length -= 1;
+        true
+    }
+
+    /// Clears the cuckoo filter.
+    pub fn clear(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            *bucket = [EMPTY; BUCKET_SIZE];
+        }
+
+        self.length = 0;
+    }
+
+    /// Returns the number of values in the cuckoo filter.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the number of fingerprint slots in the cuckoo filter.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    /// Returns the fraction of the slots that are in use.
+    pub fn load_factor(&self) -> f32 {
+        self.length as f32 / self.capacity() as f32
+    }
+
+    /// Returns the approximate memory use in bytes.
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<CuckooFilter<H>>() + self.buckets.len() * mem::size_of::<[u8; BUCKET_SIZE]>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+    use data_structures::bloom_filter::BloomFilter;
+    use test_helpers::key;
+
+    #[test]
+    fn basic_tests() {
+        let mut filter = CuckooFilter::with_capacity(10);
+        let a = vec![1, 2, 3, 4];
+
+        assert_eq!(false, filter.contains(a.as_slice()));
+        assert_eq!(Ok(()), filter.insert(a.as_slice()));
+        assert_eq!(true, filter.contains(a.as_slice()));
+        assert_eq!(1, filter.length());
+
+        assert_eq!(true, filter.delete(a.as_slice()));
+        assert_eq!(false, filter.contains(a.as_slice()));
+        assert_eq!(false, filter.delete(a.as_slice()));
+        assert_eq!(0, filter.length());
+    }
+
+    #[test]
+    fn full() {
+        let mut filter = CuckooFilter::with_capacity(64);
+        let mut inserted = 0;
+
+        while filter.insert(&key(inserted)).is_ok() {
+            inserted += 1;
+        }
+
+        // The failed insert did not change the filter.
+        let buckets = filter.buckets.clone();
+        assert_eq!(Err(CuckooFilterError::Full), filter.insert(&key(inserted)));
+        assert_eq!(buckets, filter.buckets);
+        assert_eq!(inserted as usize, filter.length());
+        assert!(filter.load_factor() > 0.5);
+
+        for i in 0 .. inserted {
+            assert!(filter.contains(&key(i)));
+        }
+
+        for i in 0 .. inserted {
+            assert!(filter.delete(&key(i)));
+        }
+
+        assert_eq!(0, filter.length());
+        assert_eq!(Ok(()), filter.insert(&key(0)));
+    }
+
+    /// Compares the false-positive rates of a cuckoo filter and a bloom filter that use the same number of bits.
+    ///
+    /// With 8-bit fingerprints, the cuckoo filter pays for deletion with a higher false-positive rate: about
+    /// `8 * 0.93 / 2^8`, or 2.9%, against the 1.6% of a bloom filter with 8.6 bits and 6 hash functions per value.
+    #[test]
+    fn false_positive_rate_against_bloom_filter() {
+        let mut cuckoo = CuckooFilter::with_capacity(4_096);
+        let mut bloom = BloomFilter::with_capacity(cuckoo.capacity() as u32 * 8, 3_800);
+        assert_eq!(cuckoo.buckets.len() * BUCKET_SIZE * 8, bloom.capacity() as usize);
+
+        for i in 0 .. 3_800 {
+            cuckoo.insert(&key(i)).unwrap();
+            bloom.push(&key(i));
+        }
+
+        let cuckoo_false_positives = (10_000 .. 110_000).filter(|&i| cuckoo.contains(&key(i))).count();
+        let bloom_false_positives = (10_000 .. 110_000).filter(|&i| bloom.contains(&key(i))).count();
+
+        assert!(2_000 < cuckoo_false_positives && cuckoo_false_positives < 4_000, "{} false positives", cuckoo_false_positives);
+        assert!(1_000 < bloom_false_positives && bloom_false_positives < 2_200, "{} false positives", bloom_false_positives);
+        assert!(bloom_false_positives < cuckoo_false_positives);
+    }
+
+    #[bench]
+    fn cuckoo_filter_contains(b: &mut Bencher) {
+        let mut filter = CuckooFilter::with_capacity(4_096);
+        for i in 0 .. 3_800 {
+            filter.insert(&key(i)).unwrap();
+        }
+
+        b.iter(|| {
+            (0 .. 1_000).filter(|&i| filter.contains(&key(i))).count()
+        })
+    }
+
+    #[bench]
+    fn bloom_filter_contains(b: &mut Bencher) {
+        let mut filter = BloomFilter::with_capacity(4_096 * 8, 3_800);
+        for i in 0 .. 3_800 {
+            filter.push(&key(i));
+        }
+
+        b.iter(|| {
+            (0 .. 1_000).filter(|&i| filter.contains(&key(i))).count()
+        })
+    }
+
+    #[test]
+    fn hash_functions() {
+        use algorithms::hash::{Fnv1a, XxHash64};
+
+        let mut fnv = CuckooFilter::with_capacity_and_hasher(1_024, Fnv1a);
+        let mut xxhash = CuckooFilter::with_capacity_and_hasher(1_024, XxHash64);
+
+        for i in 0 .. 900 {
+            fnv.insert(&key(i)).unwrap();
+            xxhash.insert(&key(i)).unwrap();
+        }
+
+        for i in 0 .. 900 {
+            assert!(fnv.contains(&key(i)));
+            assert!(xxhash.contains(&key(i)));
+        }
+    }
+}
@@ -68,6 +68,57 @@ impl<A> DoublyLinkedList<A> {
         }
     }
 
+    /// Removes the first element and returns it, or `None` if the list is empty.
+    #[inline]
+    pub fn pop_front(&mut self) -> Option<A> {
+        self.first.take().map(|mut first| {
+            match first.next.take() {
+                Some(mut next) => {
+                    next.previous = ptr::null_mut::<Node<A>>();
+                    self.first = Some(next);
+                },
+                None => self.last = ptr::null_mut::<Node<A>>(),
+            }
+
+            self.length -= 1;
+            first.value
+        })
+    }
+
+    /// Removes the element at the given index and returns it.
+    ///
+    /// This operation is `O(index)`.
+    pub fn remove_at(&mut self, index: usize) -> A {
+        if index >= self.length {
+            panic!(
+                "index out of bounds: the index {} has to be less than the length {}",
+                index,
+                self.length()
+            );
+        }
+
+        if index == 0 {
+            return self.pop_front().unwrap();
+        }
+
+        let mut previous: &mut Node<A> = self.first.as_mut().unwrap();
+        for _ in 0 .. index - 1 {
+            previous = { previous }.next.as_mut().unwrap();
+        }
+
+        let mut node = previous.next.take().unwrap();
+        match node.next.take() {
+            Some(mut next) => {
+                next.previous = previous;
+                previous.next = Some(next);
+            },
+            None => self.last = previous,
+        }
+
+        self.length -= 1;
+        node.value
+    }
+
     /// Provides a forward iterator.
     #[inline]
     pub fn iter(&self) -> Iter<A> {
@@ -78,6 +129,15 @@ impl<A> DoublyLinkedList<A> {
         }
     }
 
+    /// Provides a forward iterator with mutable references.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, A> {
+        IterMut {
+            length: self.length(),
+            next: self.first.as_deref_mut(),
+        }
+    }
+
     /// Retrieves the first element.
     #[inline]
     pub fn first(&self) -> Option<&A> {
@@ -97,7 +157,7 @@ impl<A> DoublyLinkedList<A> {
     /// Returns whether there are any elements in the list.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.first.is_some()
+        self.first.is_none()
     }
 
     /// Returns the length for this list.
@@ -161,6 +221,30 @@ impl<'a, A> Iterator for Iter<'a, A> {
     }
 }
 
+/// An iterator over the mutable references to the items of a DoublyLinkedList.
+pub struct IterMut<'a, A: 'a> {
+    next: Option<&'a mut Node<A>>,
+    length: usize,
+}
+
+impl<'a, A> Iterator for IterMut<'a, A> {
+    type Item = &'a mut A;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut A> {
+        self.next.take().map(|node| {
+            self.length -= 1;
+            self.next = node.next.as_deref_mut();
+            &mut node.value
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.length, Some(self.length))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,4 +300,37 @@ mod tests {
 
         for _ in list.iter() {}
     }
+
+    #[test]
+    fn removing() {
+        let mut list = DoublyLinkedList::new();
+        assert!(list.is_empty());
+        assert_eq!(None, list.pop_front());
+
+        for i in 0 .. 5u32 {
+            list.push_back(i);
+        }
+        assert!(!list.is_empty());
+
+        for value in list.iter_mut() {
+            *value *= 10;
+        }
+
+        assert_eq!(0, list.pop_front().unwrap());
+        assert_eq!(20, list.remove_at(1));
+        assert_eq!(40, list.remove_at(2));
+        assert_eq!(30, *list.last().unwrap());
+        assert_eq!(vec![10, 30], list.iter().cloned().collect::<Vec<u32>>());
+
+        list.push_back(50);
+        assert_eq!(50, *list.last().unwrap());
+        assert_eq!(10, list.remove_at(0));
+        assert_eq!(30, list.remove_at(0));
+        assert_eq!(50, list.remove_at(0));
+        assert!(list.is_empty());
+        assert!(list.last().is_none());
+
+        list.push_back(60);
+        assert_eq!(60, *list.first().unwrap());
+    }
 }
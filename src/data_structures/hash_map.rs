@@ -0,0 +1,670 @@
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::mem;
+use data_structures::heap_array::HeapArray;
+use algorithms::murmur::BuildMurmur3;
+
+static MIN_CAPACITY: usize = 16usize;
+
+/// The hash of an empty slot. The hashes of the keys always have the highest bit set, so they never equal it.
+static EMPTY: u64 = 0u64;
+static OCCUPIED: u64 = 1u64 << 63;
+
+/// The maximum load factor is `7/8`. Robin Hood hashing keeps the probe sequences short even at high loads.
+static MAX_LOAD_NUMERATOR: usize = 7usize;
+static MAX_LOAD_DENOMINATOR: usize = 8usize;
+
+/// An implementation of a hash map with open addressing and Robin Hood linear probing (Celis, 1986).
+///
+/// The keys are stored in a single table, and a key that collides is placed in the next free slot. During an
+/// insertion, a key that is further away from its home slot than the key in the current slot takes that slot, and
+/// the insertion continues with the displaced key. This "takes from the rich and gives to the poor", which keeps the
+/// variance of the probe lengths low, and lets a lookup stop as soon as it passes keys closer to their home slots
+/// than it is.
+///
+/// Removals use backward-shift deletion: the following keys of the cluster are shifted back by one slot, so no
+/// tombstones are needed.
+///
+/// The table grows to twice its size when the load factor would exceed `7/8`. The hashes are stored next to the
+/// entries, so growing the table does not hash the keys again.
+pub struct HashMap<K, V, S = BuildMurmur3> {
+    hashes: HeapArray<u64>,
+    entries: HeapArray<(K, V)>,
+    capacity: usize,
+    length: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq, V> HashMap<K, V, BuildMurmur3> {
+    /// Creates a new empty hash map. No memory is allocated until the first insertion.
+    pub fn new() -> HashMap<K, V, BuildMurmur3> {
+        HashMap::with_hasher(BuildMurmur3::default())
+    }
+
+    /// Creates a new empty hash map that holds at least `capacity` entries without growing.
+    pub fn with_capacity(capacity: usize) -> HashMap<K, V, BuildMurmur3> {
+        HashMap::with_capacity_and_hasher(capacity, BuildMurmur3::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
+    /// Creates a new empty hash map that uses the given hash builder to hash the keys.
+    pub fn with_hasher(hash_builder: S) -> HashMap<K, V, S> {
+        HashMap::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Creates a new empty hash map that holds at least `capacity` entries without growing, and uses the given hash
+    /// builder to hash the keys.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> HashMap<K, V, S> {
+        let mut map = HashMap {
+            hashes: HeapArray::with_capacity(0),
+            entries: HeapArray::with_capacity(0),
+            capacity: 0,
+            length: 0,
+            hash_builder: hash_builder,
+        };
+
+        if capacity > 0 {
+            map.resize(HashMap::<K, V, S>::table_size(capacity));
+        }
+
+        map
+    }
+
+    /// Returns the smallest table size that holds `length` entries within the maximum load factor.
+    fn table_size(length: usize) -> usize {
+        let slots = length * MAX_LOAD_DENOMINATOR / MAX_LOAD_NUMERATOR + 1;
+        slots.next_power_of_two().max(MIN_CAPACITY)
+    }
+
+    fn make_hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        let mut hasher = self.hash_builder.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish() | OCCUPIED
+    }
+
+    /// Returns how far the entry at the given index is from its home slot.
+    #[inline]
+    fn distance(&self, index: usize) -> usize {
+        index.wrapping_sub(self.hashes[index] as usize) & (self.capacity - 1)
+    }
+
+    /// Returns the index of the key, if the map contains it.
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize> where K: Borrow<Q>, Q: Eq + ?Sized {
+        if self.length == 0 {
+            return None;
+        }
+
+        let mask = self.capacity - 1;
+        let mut index = hash as usize & mask;
+        let mut distance = 0;
+
+        loop {
+            let slot_hash = self.hashes[index];
+
+            // A key closer to its home slot than the searched key would have been displaced by it.
+            if slot_hash == EMPTY || self.distance(index) < distance {
+                return None;
+            }
+
+            if slot_hash == hash && self.entries[index].0.borrow() == key {
+                return Some(index);
+            }
+
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+
+    /// Inserts an entry that is not in the map yet, and returns the index it was placed at.
+    ///
+    /// The table must have a free slot.
+    fn insert_hashed(&mut self, hash: u64, key: K, value: V) -> usize {
+        let mask = self.capacity - 1;
+        let mut index = hash as usize & mask;
+        let mut distance = 0;
+        let mut hash = hash;
+        let mut entry = (key, value);
+        let mut placed = None;
+
+        loop {
+            if self.hashes[index] == EMPTY {
+                self.hashes[index] = hash;
+                unsafe { self.entries.write(index, entry); }
+                self.length += 1;
+                return placed.unwrap_or(index);
+            }
+
+            // Robin Hood: take the slot from an entry that is closer to its home, and continue with that entry.
+            let slot_distance = self.distance(index);
+            if slot_distance < distance {
+                mem::swap(&mut hash, &mut self.hashes[index]);
+                mem::swap(&mut entry, &mut self.entries[index]);
+                placed = placed.or(Some(index));
+                distance = slot_distance;
+            }
+
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+
+    /// Moves the entries to a new table with the given number of slots, which must be a power of two.
+    fn resize(&mut self, capacity: usize) {
+        let old_hashes = mem::replace(&mut self.hashes, HeapArray::with_capacity(capacity));
+        let old_entries = mem::replace(&mut self.entries, HeapArray::with_capacity(capacity));
+        let old_capacity = self.capacity;
+
+        self.capacity = capacity;
+        self.length = 0;
+        for i in 0 .. capacity {
+            self.hashes[i] = EMPTY;
+        }
+
+        for i in 0 .. old_capacity {
+            if old_hashes[i] != EMPTY {
+                let (key, value) = unsafe { old_entries.read(i) };
+                self.insert_hashed(old_hashes[i], key, value);
+            }
+        }
+    }
+
+    /// Grows the table if one more entry would exceed the maximum load factor.
+    fn reserve_one(&mut self) {
+        if (self.length + 1) * MAX_LOAD_DENOMINATOR > self.capacity * MAX_LOAD_NUMERATOR {
+            let capacity = if self.capacity == 0 { MIN_CAPACITY } else { self.capacity * 2 };
+            self.resize(capacity);
+        }
+    }
+
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map already contained the key, the value is replaced and the old value is returned. The key is not
+    /// replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.make_hash(&key);
+        if let Some(index) = self.find(hash, &key) {
+            return Some(mem::replace(&mut self.entries[index].1, value));
+        }
+
+        self.reserve_one();
+        self.insert_hashed(hash, key, value);
+        None
+    }
+
+    /// Returns a reference to the value of the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V> where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.make_hash(key);
+        self.find(hash, key).map(|index| &self.entries[index].1)
+    }
+
+    /// Returns a mutable reference to the value of the key.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.make_hash(key);
+        match self.find(hash, key) {
+            Some(index) => Some(&mut self.entries[index].1),
+            None => None,
+        }
+    }
+
+    /// Returns true if the map contains the key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.make_hash(key);
+        self.find(hash, key).is_some()
+    }
+
+    /// Removes the entry at the given index, and shifts the following entries of the cluster back by one slot.
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        let mask = self.capacity - 1;
+        let entry = unsafe { self.entries.read(index) };
+        let mut index = index;
+        let mut next = (index + 1) & mask;
+
+        // The shift stops at an empty slot, or at an entry that is already in its home slot.
+        while self.hashes[next] != EMPTY && self.distance(next) > 0 {
+            self.hashes[index] = self.hashes[next];
+            unsafe {
+                let moved = self.entries.read(next);
+                self.entries.write(index, moved);
+            }
+
+            index = next;
+            next = (next + 1) & mask;
+        }
+
+        self.hashes[index] = EMPTY;
+        self.length -= 1;
+        entry
+    }
+
+    /// Removes the key from the map, and returns its value if the map contained it.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q>, Q: Hash + Eq + ?Sized {
+        let hash = self.make_hash(key);
+        self.find(hash, key).map(|index| self.remove_at(index).1)
+    }
+
+    /// Returns the entry of the key, for in-place manipulation.
+    ///
+    /// The table may grow, even if the map already contains the key.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        self.reserve_one();
+
+        let hash = self.make_hash(&key);
+        match self.find(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index: index }),
+            None => Entry::Vacant(VacantEntry { map: self, hash: hash, key: key }),
+        }
+    }
+
+    /// Keeps only the entries for which the predicate returns true.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut predicate: F) {
+        let mut removed = false;
+
+        // Removing while iterating would shift entries that were already visited back into the unvisited slots, so
+        // the entries are only dropped here, and the table is rebuilt afterwards.
+        for i in 0 .. self.capacity {
+            if self.hashes[i] != EMPTY {
+                let keep = {
+                    let entry = &mut self.entries[i];
+                    predicate(&entry.0, &mut entry.1)
+                };
+
+                if !keep {
+                    self.hashes[i] = EMPTY;
+                    self.length -= 1;
+                    unsafe { drop(self.entries.read(i)); }
+                    removed = true;
+                }
+            }
+        }
+
+        if removed {
+            let capacity = self.capacity;
+            self.resize(capacity);
+        }
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    /// Returns an iterator over the entries, in an arbitrary order.
+    pub fn iter(&self) -> Iter<'_, K, V, S> {
+        Iter {
+            map: self,
+            index: 0,
+            remaining: self.length,
+        }
+    }
+
+    /// Removes all entries. The capacity is kept.
+    pub fn clear(&mut self) {
+        for i in 0 .. self.capacity {
+            if self.hashes[i] != EMPTY {
+                self.hashes[i] = EMPTY;
+                unsafe { drop(self.entries.read(i)); }
+            }
+        }
+
+        self.length = 0;
+    }
+
+    /// Returns the number of entries.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns true if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the number of entries the map holds without growing.
+    pub fn capacity(&self) -> usize {
+        self.capacity * MAX_LOAD_NUMERATOR / MAX_LOAD_DENOMINATOR
+    }
+}
+
+impl<K: Hash + Eq, V> Default for HashMap<K, V, BuildMurmur3> {
+    fn default() -> HashMap<K, V, BuildMurmur3> {
+        HashMap::new()
+    }
+}
+
+impl<K, V, S> Drop for HashMap<K, V, S> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// An iterator over the entries of a `HashMap`.
+pub struct Iter<'a, K: 'a, V: 'a, S: 'a> {
+    map: &'a HashMap<K, V, S>,
+    index: usize,
+    remaining: usize,
+}
+
+impl<'a, K, V, S> Iterator for Iter<'a, K, V, S> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        while self.index < self.map.capacity {
+            let index = self.index;
+            self.index += 1;
+
+            if self.map.hashes[index] != EMPTY {
+                self.remaining -= 1;
+                let entry = &self.map.entries[index];
+                return Some((&entry.0, &entry.1));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, S>;
+
+    fn into_iter(self) -> Iter<'a, K, V, S> {
+        self.iter()
+    }
+}
+
+/// An entry of a `HashMap`, which is either occupied or vacant.
+pub enum Entry<'a, K: 'a, V: 'a, S: 'a> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+/// An entry of a key the map contains.
+pub struct OccupiedEntry<'a, K: 'a, V: 'a, S: 'a> {
+    map: &'a mut HashMap<K, V, S>,
+    index: usize,
+}
+
+/// An entry of a key the map does not contain.
+pub struct VacantEntry<'a, K: 'a, V: 'a, S: 'a> {
+    map: &'a mut HashMap<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> Entry<'a, K, V, S> {
+    /// Returns the value of the entry, and inserts the default first if the entry is vacant.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Returns the value of the entry, and inserts the result of the function first if the entry is vacant.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => &entry.key,
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.map.entries[self.index].0
+    }
+
+    /// Returns the value of the entry.
+    pub fn get(&self) -> &V {
+        &self.map.entries[self.index].1
+    }
+
+    /// Returns a mutable reference to the value of the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.entries[self.index].1
+    }
+
+    /// Converts the entry into a mutable reference to its value, which lives as long as the map is borrowed.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.entries[self.index].1
+    }
+
+    /// Replaces the value of the entry, and returns the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry from the map, and returns its value.
+    pub fn remove(self) -> V {
+        self.map.remove_at(self.index).1
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    /// Inserts the value, and returns a mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        // `HashMap::entry()` made room for the entry already.
+        let index = self.map.insert_hashed(self.hash, self.key, value);
+        &mut self.map.entries[index].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections;
+    use std::rc::Rc;
+    use test::{Bencher, black_box};
+
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn basic_tests() {
+        let mut map = HashMap::new();
+        assert!(map.is_empty());
+        assert_eq!(None, map.get("a"));
+
+        assert_eq!(None, map.insert("a".to_string(), 1));
+        assert_eq!(None, map.insert("b".to_string(), 2));
+        assert_eq!(Some(1), map.insert("a".to_string(), 3));
+        assert_eq!(2, map.length());
+        assert_eq!(Some(&3), map.get("a"));
+        assert!(map.contains_key("b"));
+
+        *map.get_mut("b").unwrap() += 10;
+        assert_eq!(Some(&12), map.get("b"));
+
+        assert_eq!(Some(3), map.remove("a"));
+        assert_eq!(None, map.remove("a"));
+        assert_eq!(None, map.get("a"));
+        assert_eq!(1, map.length());
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(None, map.get("b"));
+    }
+
+    #[test]
+    fn entry() {
+        let mut map = HashMap::new();
+        for word in "the quick brown fox jumps over the lazy dog the end".split(' ') {
+            *map.entry(word).or_insert(0) += 1;
+        }
+
+        assert_eq!(Some(&3), map.get("the"));
+        assert_eq!(Some(&1), map.get("fox"));
+        assert_eq!(9, map.length());
+
+        match map.entry("fox") {
+            Entry::Occupied(entry) => assert_eq!(1, entry.remove()),
+            Entry::Vacant(_) => panic!("fox should be occupied"),
+        }
+        assert_eq!(None, map.get("fox"));
+        {
+            let entry = map.entry("cat");
+            assert_eq!(&"cat", entry.key());
+        }
+        assert_eq!(5, *map.entry("cat").or_insert_with(|| 5));
+    }
+
+    /// Compares the map to std's map over a mix of insertions and removals, which exercises the Robin Hood
+    /// displacement, the backward shifts and the resizing.
+    #[test]
+    fn matches_std_hash_map() {
+        let mut map = HashMap::new();
+        let mut expected = collections::HashMap::new();
+
+        for i in 0 .. 20_000u32 {
+            let key = i.wrapping_mul(2654435761) % 5_000;
+            if i % 3 == 0 {
+                assert_eq!(expected.remove(&key), map.remove(&key));
+            } else {
+                assert_eq!(expected.insert(key, i), map.insert(key, i));
+            }
+            assert_eq!(expected.len(), map.length());
+        }
+
+        for key in 0 .. 5_000 {
+            assert_eq!(expected.get(&key), map.get(&key));
+        }
+
+        let mut entries: Vec<(u32, u32)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        let mut expected_entries: Vec<(u32, u32)> = expected.iter().map(|(&k, &v)| (k, v)).collect();
+        entries.sort();
+        expected_entries.sort();
+        assert_eq!(expected_entries, entries);
+        assert_eq!(map.length(), map.iter().size_hint().0);
+    }
+
+    #[test]
+    fn retain() {
+        let mut map = HashMap::with_capacity(1_000);
+        let capacity = map.capacity();
+        for i in 0 .. 1_000 {
+            map.insert(i, i * 2);
+        }
+        assert_eq!(capacity, map.capacity());
+
+        map.retain(|&k, v| {
+            *v += 1;
+            k % 3 == 0
+        });
+
+        assert_eq!(334, map.length());
+        for i in 0 .. 1_000 {
+            let expected = if i % 3 == 0 { Some(i * 2 + 1) } else { None };
+            assert_eq!(expected.as_ref(), map.get(&i));
+        }
+    }
+
+    #[test]
+    fn drops_values() {
+        let value = Rc::new(());
+        {
+            let mut map = HashMap::new();
+            for i in 0 .. 100 {
+                map.insert(i, value.clone());
+            }
+            map.remove(&0);
+            map.retain(|&k, _| k < 50);
+            assert_eq!(50, Rc::strong_count(&value));
+        }
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    /// Every value is dropped exactly once, whether it is replaced, moved to a grown table, cleared or dropped with
+    /// the map.
+    #[test]
+    fn drops_values_once() {
+        let drops = Rc::new(Cell::new(0));
+        {
+            let mut map = HashMap::new();
+            for i in 0 .. 1_000 {
+                map.insert(i, DropCounter(drops.clone()));
+            }
+            assert_eq!(0, drops.get());
+
+            map.insert(0, DropCounter(drops.clone()));
+            assert_eq!(1, drops.get());
+
+            map.clear();
+            assert_eq!(1_001, drops.get());
+
+            for i in 0 .. 100 {
+                map.insert(i, DropCounter(drops.clone()));
+            }
+        }
+        assert_eq!(1_101, drops.get());
+    }
+
+    #[test]
+    fn zero_sized_entries() {
+        let mut map = HashMap::new();
+        assert_eq!(None, map.insert((), ()));
+        assert_eq!(Some(()), map.insert((), ()));
+        assert_eq!(Some(&()), map.get(&()));
+        assert_eq!(1, map.length());
+        assert_eq!(Some(()), map.remove(&()));
+        assert!(map.is_empty());
+    }
+
+    #[bench]
+    fn inserting(b: &mut Bencher) {
+        b.iter(|| {
+            let mut map = HashMap::new();
+            for i in 0 .. 10_000u64 {
+                map.insert(i, i);
+            }
+            black_box(map.length())
+        })
+    }
+
+    #[bench]
+    fn inserting_std(b: &mut Bencher) {
+        b.iter(|| {
+            let mut map = collections::HashMap::new();
+            for i in 0 .. 10_000u64 {
+                map.insert(i, i);
+            }
+            black_box(map.len())
+        })
+    }
+
+    #[bench]
+    fn getting(b: &mut Bencher) {
+        let mut map = HashMap::new();
+        for i in 0 .. 10_000u64 {
+            map.insert(i, i);
+        }
+
+        b.iter(|| (0 .. 20_000u64).filter(|i| map.get(i).is_some()).count())
+    }
+
+    #[bench]
+    fn getting_std(b: &mut Bencher) {
+        let mut map = collections::HashMap::new();
+        for i in 0 .. 10_000u64 {
+            map.insert(i, i);
+        }
+
+        b.iter(|| (0 .. 20_000u64).filter(|i| map.get(i).is_some()).count())
+    }
+}
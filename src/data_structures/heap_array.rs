@@ -1,15 +1,19 @@
 use std::slice::from_raw_parts_mut;
 use std::slice::from_raw_parts;
-use alloc::heap::allocate;
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
 use std::usize;
 use std::ptr::copy_nonoverlapping;
 use std::mem;
 use std::ptr;
+use std::ptr::NonNull;
 use std::ops::{Index, IndexMut};
 
 /// An implementation of a fixed-size mutable array, which is allocated on the heap.
 ///
 /// Minimum memory requirement is two pointer sized integers, e.g. 16-bytes on 64-bit system.
+///
+/// The array does not know which of its elements were written, so dropping it frees the memory without dropping
+/// the elements. The owner has to drop or move them out before.
 pub struct HeapArray<A> {
     pointer: *mut A,
     capacity: usize,
@@ -23,23 +27,27 @@ impl<A> HeapArray<A> {
     pub fn with_capacity(capacity: usize) -> HeapArray<A> {
         let a_size = mem::size_of::<A>();
 
+        // Nothing is allocated for zero-sized A's or zero capacity, but the pointer still has to be non-null and
+        // aligned for the slices of the array.
+        let dangling = NonNull::dangling().as_ptr();
+
         if a_size == 0 {
             HeapArray {
-                pointer: 0 as *mut A,
+                pointer: dangling,
                 capacity: usize::MAX, // Empty sized A's yield infinite capacity.
             }
         } else if capacity == 0 {
             HeapArray {
-                pointer: 0 as *mut A,
+                pointer: dangling,
                 capacity: 0,
             }
         } else {
-            let bytes = capacity.checked_mul(a_size).expect("capacity overflow");
+            let layout = Layout::array::<A>(capacity).expect("capacity overflow");
             let pointer = unsafe {
-                allocate(bytes, mem::align_of::<A>())
+                alloc(layout)
             };
 
-            if pointer.is_null() { ::alloc::oom() }
+            if pointer.is_null() { handle_alloc_error(layout) }
 
             HeapArray {
                 pointer: pointer as *mut A,
@@ -73,12 +81,43 @@ impl<A> HeapArray<A> {
         }
     }
 
+    /// Reads the element at the given index without moving it out of the array.
+    ///
+    /// # Safety
+    ///
+    /// The element must have been written before. The array keeps the bits of the element, so it must not be read
+    /// or dropped twice.
+    pub unsafe fn read(&self, index: usize) -> A {
+        ptr::read(self.pointer.offset(index as isize))
+    }
+
+    /// Writes the element at the given index without dropping the previous element.
+    ///
+    /// # Safety
+    ///
+    /// The index must be within the capacity. A previous element at the index is leaked rather than dropped.
+    pub unsafe fn write(&mut self, index: usize, element: A) {
+        ptr::write(self.pointer.offset(index as isize), element)
+    }
+
     /// Returns the capacity for this array.
     pub fn capacity(&self) -> usize {
         self.capacity
     }
 }
 
+impl<A> Drop for HeapArray<A> {
+    fn drop(&mut self) {
+        let a_size = mem::size_of::<A>();
+
+        if a_size != 0 && self.capacity != 0 {
+            unsafe {
+                dealloc(self.pointer as *mut u8, Layout::array::<A>(self.capacity).unwrap());
+            }
+        }
+    }
+}
+
 impl<A> AsRef<[A]> for HeapArray<A> {
     #[inline]
     fn as_ref(&self) -> &[A] {
@@ -147,3 +186,20 @@ fn copy_test() {
 
     assert_eq!(4, b.capacity());
 }
+
+#[test]
+fn zero_sized_test() {
+    let mut a = HeapArray::with_capacity(10);
+
+    assert_eq!(usize::MAX, a.capacity());
+
+    a[0] = ();
+    a[1_000] = ();
+    a.swap(0, 1_000);
+
+    unsafe {
+        a.write(5, ());
+        assert_eq!((), a.read(5));
+    }
+    assert_eq!((), a[1_000]);
+}
@@ -0,0 +1,229 @@
+use std::hash::{Hash, Hasher};
+use algorithms::hash::{HashFunction, Murmur3X64};
+
+/// An implementation of the HyperLogLog cardinality estimator (Flajolet et al., 2007).
+///
+/// HyperLogLog estimates the number of distinct values it has seen, using `2^precision` one-byte registers.
+/// Every value is hashed to 64 bits. The first `precision` bits select a register, which keeps the maximum
+/// number of leading zeros seen in the remaining bits. The standard error is `1.04 / sqrt(2^precision)`,
+/// e.g. 0.81% with the default precision of 14, which uses 16 KiB.
+///
+/// The hash function has to produce 64 bits, since the register is selected by the highest bits.
+pub struct HyperLogLog<H = Murmur3X64> {
+    registers: Vec<u8>,
+    precision: u32,
+    hash_function: H,
+}
+
+/// Collects the bytes fed to a `Hasher`, so that hashable values can be hashed with a `HashFunction`.
+struct ByteCollector {
+    bytes: Vec<u8>,
+}
+
+impl Hasher for ByteCollector {
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("the collected bytes are hashed by the HyperLogLog")
+    }
+}
+
+impl HyperLogLog {
+    /// Constructs a new HyperLogLog with a precision of 14.
+    pub fn new() -> HyperLogLog {
+        HyperLogLog::with_precision(14)
+    }
+
+    /// Constructs a new HyperLogLog with `2^precision` registers.
+    ///
+    /// The precision has to be within `[4, 18]`.
+    pub fn with_precision(precision: u32) -> HyperLogLog {
+        HyperLogLog::with_precision_and_hasher(precision, Murmur3X64)
+    }
+}
+
+impl<H: HashFunction> HyperLogLog<H> {
+    /// Constructs a new HyperLogLog with `2^precision` registers, that uses the given hash function.
+    pub fn with_precision_and_hasher(precision: u32, hash_function: H) -> HyperLogLog<H> {
+        if !(4 ..= 18).contains(&precision) {
+            panic!("the precision {} has to be within [4, 18]", precision);
+        }
+
+        HyperLogLog {
+            registers: vec![0u8; 1 << precision],
+            precision: precision,
+            hash_function: hash_function,
+        }
+    }
+
+    fn add_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rest = hash << self.precision;
+        let rank = if rest == 0 {
+            64 - self.precision + 1
+        } else {
+            rest.leading_zeros() + 1
+        };
+
+        if self.registers[index] < rank as u8 {
+            self.registers[index] = rank as u8;
+        }
+    }
+
+    /// Adds a value.
+    pub fn add(&mut self, data: &[u8]) {
+        let hash = self.hash_function.hash(data, 0);
+        self.add_hash(hash);
+    }
+
+    /// Adds a hashable value.
+    pub fn add_hashable<T: Hash>(&mut self, value: &T) {
+        let mut collector = ByteCollector { bytes: Vec::new() };
+        value.hash(&mut collector);
+        self.add(&collector.bytes);
+    }
+
+    /// Returns the estimated number of distinct values that were added.
+    pub fn count(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let sum = self.registers.iter().fold(0f64, |acc, &register| acc + 2f64.powi(-(register as i32)));
+        let estimate = alpha * m * m / sum;
+
+        // Small range correction: linear counting is more accurate while many registers are still zero.
+        if estimate <= 2.5 * m {
+            let zeros = self.registers.iter().filter(|&&register| register == 0).count();
+            if zeros > 0 {
+                return (m * (m / zeros as f64).ln()).round() as u64;
+            }
+        }
+
+        // Large range correction: hash collisions become likely when nearing the size of the hash space.
+        let hash_space = 2f64.powi(64);
+        if estimate > hash_space / 30.0 {
+            return (-hash_space * (1.0 - estimate / hash_space).ln()).round() as u64;
+        }
+
+        estimate.round() as u64
+    }
+
+    /// Merges the other HyperLogLog into this one.
+    ///
+    /// The result is identical to a HyperLogLog that had the values of both added to it. Both must have the same
+    /// precision.
+    pub fn merge(&mut self, other: &HyperLogLog<H>) {
+        if self.precision != other.precision {
+            panic!("cannot merge HyperLogLogs with precisions {} and {}", self.precision, other.precision);
+        }
+
+        for (register, &other_register) in self.registers.iter_mut().zip(other.registers.iter()) {
+            if *register < other_register {
+                *register = other_register;
+            }
+        }
+    }
+
+    /// Clears the HyperLogLog.
+    pub fn clear(&mut self) {
+        for register in self.registers.iter_mut() {
+            *register = 0;
+        }
+    }
+
+    /// Returns the precision.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::key;
+
+    fn relative_error(estimate: u64, actual: u64) -> f64 {
+        (estimate as f64 - actual as f64).abs() / actual as f64
+    }
+
+    #[test]
+    fn basic_tests() {
+        let mut hll = HyperLogLog::new();
+        assert_eq!(0, hll.count());
+
+        hll.add(&[1u8, 2, 3, 4]);
+        hll.add(&[1u8, 2, 3, 4]);
+        hll.add_hashable(&"hello");
+        hll.add_hashable(&"hello");
+        assert_eq!(2, hll.count());
+
+        hll.clear();
+        assert_eq!(0, hll.count());
+    }
+
+    /// The estimates should stay within three standard errors (2.4% at precision 14).
+    #[test]
+    fn error_bounds() {
+        let mut hll = HyperLogLog::with_precision(14);
+        let mut added = 0;
+
+        for &cardinality in [100u32, 1_000, 10_000, 100_000, 500_000].iter() {
+            while added < cardinality {
+                hll.add(&key(added));
+                added += 1;
+            }
+
+            // Adding the same values again must not change the estimate.
+            let estimate = hll.count();
+            hll.add(&key(0));
+            assert_eq!(estimate, hll.count());
+
+            let error = relative_error(estimate, cardinality as u64);
+            assert!(error < 0.024, "estimated {} for {} values", estimate, cardinality);
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = HyperLogLog::with_precision(12);
+        let mut b = HyperLogLog::with_precision(12);
+        let mut union = HyperLogLog::with_precision(12);
+
+        for i in 0 .. 20_000 {
+            a.add(&key(i));
+            union.add(&key(i));
+        }
+
+        for i in 10_000 .. 30_000 {
+            b.add(&key(i));
+            union.add(&key(i));
+        }
+
+        a.merge(&b);
+        assert_eq!(union.count(), a.count());
+        assert!(relative_error(a.count(), 30_000) < 0.05);
+    }
+
+    #[test]
+    fn hash_functions() {
+        use algorithms::hash::{CityHash64, XxHash64};
+
+        let mut city = HyperLogLog::with_precision_and_hasher(12, CityHash64);
+        let mut xxhash = HyperLogLog::with_precision_and_hasher(12, XxHash64);
+
+        for i in 0 .. 10_000 {
+            city.add(&key(i));
+            xxhash.add(&key(i));
+        }
+
+        assert!(relative_error(city.count(), 10_000) < 0.05, "estimated {}", city.count());
+        assert!(relative_error(xxhash.count(), 10_000) < 0.05, "estimated {}", xxhash.count());
+    }
+}
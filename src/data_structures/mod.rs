@@ -8,3 +8,21 @@ pub mod doubly_linked_list;
 pub mod binary_heap;
 /// An implementation of a bloom filter.
 pub mod bloom_filter;
+/// An implementation of a scalable bloom filter.
+pub mod scalable_bloom_filter;
+/// An implementation of a cuckoo filter.
+pub mod cuckoo_filter;
+/// An implementation of a blocked bloom filter.
+pub mod blocked_bloom_filter;
+/// An implementation of a quotient filter.
+pub mod quotient_filter;
+/// An implementation of the HyperLogLog cardinality estimator.
+pub mod hyperloglog;
+/// An implementation of a Count-Min sketch.
+pub mod count_min_sketch;
+/// An implementation of a hash map with Robin Hood hashing.
+pub mod hash_map;
+/// An implementation of a hash map with separate chaining.
+pub mod chained_hash_map;
+/// An implementation of a hash set with separate chaining.
+pub mod chained_hash_set;
@@ -0,0 +1,601 @@
+use std::cmp::max;
+use algorithms::hash::{HashFunction, Murmur3X64};
+
+/// The slot's quotient has a run in the filter.
+static OCCUPIED: u8 = 1u8;
+
+/// The slot's remainder belongs to the same run as the previous slot.
+static CONTINUATION: u8 = 2u8;
+
+/// The slot's remainder is not in its canonical slot.
+static SHIFTED: u8 = 4u8;
+
+/// An error that occurs when inserting into a quotient filter.
+#[derive(Debug, PartialEq)]
+pub enum QuotientFilterError {
+    /// The filter has no room for the value. The value was not added.
+    Full,
+}
+
+/// An implementation of a quotient filter (Bender et al., 2012).
+///
+/// A quotient filter stores a `q + r` bit fingerprint of each value. The upper `q` bits, the quotient, select
+/// the canonical slot, and the lower `r` bits, the remainder, are stored in the table. Colliding remainders
+/// are kept sorted in runs next to their canonical slot, which is tracked with three metadata bits per slot.
+///
+/// Since the fingerprints can be recovered from the table, a quotient filter can be resized and merged
+/// without the original values. Fingerprints are stored once, like in a set. The false-positive rate is
+/// roughly `load_factor / 2^r`.
+///
+/// The fingerprints are the lower `q + r` bits of the hash, so the hash function has to produce at least as many.
+pub struct QuotientFilter<H = Murmur3X64> {
+    quotient_bits: u32,
+    remainder_bits: u32,
+    remainders: Vec<u64>,
+    metadata: Vec<u8>,
+    length: usize,
+    hash_function: H,
+}
+
+fn mask(bits: u32) -> u64 {
+    if bits >= 64 { !0u64 } else { (1u64 << bits) - 1 }
+}
+
+impl QuotientFilter {
+    /// Constructs a new quotient filter with `2^quotient_bits` slots of `remainder_bits` each.
+    ///
+    /// The fingerprints have `quotient_bits + remainder_bits` bits, which may not exceed 64.
+    pub fn new(quotient_bits: u32, remainder_bits: u32) -> QuotientFilter {
+        QuotientFilter::with_hasher(quotient_bits, remainder_bits, Murmur3X64)
+    }
+}
+
+impl<H: HashFunction + Clone> QuotientFilter<H> {
+    /// Constructs a new quotient filter with `2^quotient_bits` slots of `remainder_bits` each, that uses the given
+    /// hash function.
+    pub fn with_hasher(quotient_bits: u32, remainder_bits: u32, hash_function: H) -> QuotientFilter<H> {
+        if quotient_bits == 0 || remainder_bits == 0 || quotient_bits + remainder_bits > 64 {
+            panic!(
+                "invalid quotient filter size: {} quotient bits and {} remainder bits",
+                quotient_bits,
+                remainder_bits
+            );
+        }
+
+        let size = 1usize << quotient_bits;
+
+        QuotientFilter {
+            quotient_bits: quotient_bits,
+            remainder_bits: remainder_bits,
+            remainders: vec![0u64; size],
+            metadata: vec![0u8; size],
+            length: 0,
+            hash_function: hash_function,
+        }
+    }
+
+    fn fingerprint(&self, data: &[u8]) -> u64 {
+        self.hash_function.hash(data, 0) & mask(self.quotient_bits + self.remainder_bits)
+    }
+
+    fn split(&self, fingerprint: u64) -> (usize, u64) {
+        ((fingerprint >> self.remainder_bits) as usize, fingerprint & mask(self.remainder_bits))
+    }
+
+    #[inline]
+    fn increment(&self, index: usize) -> usize {
+        (index + 1) & (self.remainders.len() - 1)
+    }
+
+    #[inline]
+    fn decrement(&self, index: usize) -> usize {
+        index.wrapping_sub(1) & (self.remainders.len() - 1)
+    }
+
+    #[inline]
+    fn is_empty_slot(&self, index: usize) -> bool {
+        self.metadata[index] == 0
+    }
+
+    #[inline]
+    fn is_occupied(&self, index: usize) -> bool {
+        self.metadata[index] & OCCUPIED != 0
+    }
+
+    #[inline]
+    fn is_continuation(&self, index: usize) -> bool {
+        self.metadata[index] & CONTINUATION != 0
+    }
+
+    #[inline]
+    fn is_shifted(&self, index: usize) -> bool {
+        self.metadata[index] & SHIFTED != 0
+    }
+
+    /// Finds the slot where the run of the given quotient starts, or would start if it had no run yet.
+    fn find_run_start(&self, quotient: usize) -> usize {
+        // Walk back to the start of the cluster, which is in its canonical slot.
+        let mut cluster = quotient;
+        while self.is_shifted(cluster) {
+            cluster = self.decrement(cluster);
+        }
+
+        // Walk forward run by run, until we reach the run of the quotient.
+        let mut run = cluster;
+        while cluster != quotient {
+            loop {
+                run = self.increment(run);
+                if !self.is_continuation(run) {
+                    break;
+                }
+            }
+
+            loop {
+                cluster = self.increment(cluster);
+                if self.is_occupied(cluster) {
+                    break;
+                }
+            }
+        }
+
+        run
+    }
+
+    /// Inserts the remainder at the given slot, and shifts the following remainders right until an empty slot.
+    ///
+    /// The occupied bits stay in place, since they belong to the slots rather than to the remainders.
+    fn insert_into(&mut self, index: usize, remainder: u64, metadata: u8) {
+        let (mut index, mut remainder, mut metadata) = (index, remainder, metadata);
+
+        loop {
+            let was_empty = self.is_empty_slot(index);
+            let (previous_remainder, mut previous_metadata) = (self.remainders[index], self.metadata[index]);
+            let occupied = previous_metadata & OCCUPIED;
+
+            if !was_empty {
+                previous_metadata |= SHIFTED;
+            }
+
+            self.remainders[index] = remainder;
+            self.metadata[index] = metadata | occupied;
+
+            remainder = previous_remainder;
+            metadata = previous_metadata & !OCCUPIED;
+            index = self.increment(index);
+
+            if was_empty {
+                break;
+            }
+        }
+    }
+
+    fn insert_fingerprint(&mut self, fingerprint: u64) -> Result<(), QuotientFilterError> {
+        if self.contains_fingerprint(fingerprint) {
+            return Ok(());
+        }
+
+        // One slot is always kept empty, so that clusters can be told apart.
+        if self.length + 1 >= self.capacity() {
+            return Err(QuotientFilterError::Full);
+        }
+
+        let (quotient, remainder) = self.split(fingerprint);
+
+        if self.is_empty_slot(quotient) {
+            self.remainders[quotient] = remainder;
+            self.metadata[quotient] = OCCUPIED;
+            self.length += 1;
+            return Ok(());
+        }
+
+        let run_exists = self.is_occupied(quotient);
+        self.metadata[quotient] |= OCCUPIED;
+
+        let start = self.find_run_start(quotient);
+        let mut index = start;
+        let mut metadata = 0u8;
+
+        if run_exists {
+            // Runs are sorted, so find the first remainder that is greater.
+            loop {
+                if self.remainders[index] > remainder {
+                    break;
+                }
+
+                index = self.increment(index);
+                if !self.is_continuation(index) {
+                    break;
+                }
+            }
+
+            if index == start {
+                self.metadata[start] |= CONTINUATION;
+            } else {
+                metadata |= CONTINUATION;
+            }
+        }
+
+        if index != quotient {
+            metadata |= SHIFTED;
+        }
+
+        self.insert_into(index, remainder, metadata);
+        self.length += 1;
+        Ok(())
+    }
+
+    fn contains_fingerprint(&self, fingerprint: u64) -> bool {
+        let (quotient, remainder) = self.split(fingerprint);
+
+        if !self.is_occupied(quotient) {
+            return false;
+        }
+
+        let mut index = self.find_run_start(quotient);
+        loop {
+            if self.remainders[index] == remainder {
+                return true;
+            } else if self.remainders[index] > remainder {
+                return false;
+            }
+
+            index = self.increment(index);
+            if !self.is_continuation(index) {
+                return false;
+            }
+        }
+    }
+
+    /// Returns the fingerprints of the contiguous non-empty slots, starting from a slot in its canonical position.
+    fn fingerprints_from(&self, start: usize) -> Vec<u64> {
+        let (mut quotient, mut index) = (start, start);
+        let mut fingerprints = Vec::new();
+
+        while !self.is_empty_slot(index) {
+            if !self.is_continuation(index) {
+                if self.is_shifted(index) {
+                    loop {
+                        quotient = self.increment(quotient);
+                        if self.is_occupied(quotient) {
+                            break;
+                        }
+                    }
+                } else {
+                    quotient = index;
+                }
+            }
+
+            fingerprints.push(((quotient as u64) << self.remainder_bits) | self.remainders[index]);
+            index = self.increment(index);
+        }
+
+        fingerprints
+    }
+
+    /// Returns the fingerprints of every value in the filter.
+    fn fingerprints(&self) -> Vec<u64> {
+        let mut fingerprints = Vec::with_capacity(self.length);
+
+        let empty = match (0 .. self.capacity()).find(|&index| self.is_empty_slot(index)) {
+            Some(index) => index,
+            None => return fingerprints,
+        };
+
+        let mut offset = 1;
+        while offset < self.capacity() {
+            let index = (empty + offset) & (self.capacity() - 1);
+
+            if self.is_empty_slot(index) {
+                offset += 1;
+            } else {
+                let cluster = self.fingerprints_from(index);
+                offset += cluster.len();
+                fingerprints.extend(cluster);
+            }
+        }
+
+        fingerprints
+    }
+
+    /// Inserts a new value to the quotient filter.
+    ///
+    /// Returns `QuotientFilterError::Full` if every slot but one is in use. The filter can be grown with `resize()`.
+    pub fn insert(&mut self, data: &[u8]) -> Result<(), QuotientFilterError> {
+        let fingerprint = self.fingerprint(data);
+        self.insert_fingerprint(fingerprint)
+    }
+
+    /// Returns false if the data was definitely not added to the quotient filter, and true if it may have been.
+    pub fn contains(&self, data: &[u8]) -> bool {
+        self.contains_fingerprint(self.fingerprint(data))
+    }
+
+    /// Deletes a value from the quotient filter. Returns whether the value (possibly) was in the filter.
+    ///
+    /// Since fingerprints are stored once, this also deletes any other value that shares the fingerprint.
+    pub fn delete(&mut self, data: &[u8]) -> bool {
+        let fingerprint = self.fingerprint(data);
+
+        if !self.contains_fingerprint(fingerprint) {
+            return false;
+        }
+
+        let (quotient, _) = self.split(fingerprint);
+
+        let mut start = quotient;
+        while self.is_shifted(start) {
+            start = self.decrement(start);
+        }
+
+        // Rebuilding the slots from the cluster start up to the next empty slot keeps the metadata consistent.
+        let fingerprints = self.fingerprints_from(start);
+
+        let mut index = start;
+        for _ in 0 .. fingerprints.len() {
+            self.remainders[index] = 0;
+            self.metadata[index] = 0;
+            index = self.increment(index);
+        }
+
+        self.length -= fingerprints.len();
+
+        for other in fingerprints.into_iter().filter(|&other| other != fingerprint) {
+            self.insert_fingerprint(other).unwrap();
+        }
+
+        true
+    }
+
+    /// Doubles the number of slots.
+    ///
+    /// One bit of every remainder is moved to its quotient, so the false-positive rate stays the same for the
+    /// values already in the filter.
+    pub fn resize(&mut self) {
+        if self.remainder_bits == 1 {
+            panic!("cannot resize a quotient filter with a single remainder bit");
+        }
+
+        let mut filter = QuotientFilter::with_hasher(
+            self.quotient_bits + 1,
+            self.remainder_bits - 1,
+            self.hash_function.clone()
+        );
+
+        for fingerprint in self.fingerprints() {
+            filter.insert_fingerprint(fingerprint).unwrap();
+        }
+
+        *self = filter;
+    }
+
+    /// Merges two quotient filters into a new one, that contains the values of both.
+    ///
+    /// Both filters must use fingerprints of the same size. The new filter is large enough for all of the values,
+    /// which takes one more quotient bit whenever the fingerprints fill half of the slots. Returns
+    /// `QuotientFilterError::Full` if they do not fit with at least one remainder bit left.
+    pub fn merge(&self, other: &QuotientFilter<H>) -> Result<QuotientFilter<H>, QuotientFilterError> {
+        let fingerprint_bits = self.quotient_bits + self.remainder_bits;
+
+        if fingerprint_bits != other.quotient_bits + other.remainder_bits {
+            panic!(
+                "cannot merge quotient filters with {} and {} bit fingerprints",
+                fingerprint_bits,
+                other.quotient_bits + other.remainder_bits
+            );
+        }
+
+        let mut fingerprints = self.fingerprints();
+        fingerprints.extend(other.fingerprints());
+        fingerprints.sort();
+        fingerprints.dedup();
+
+        let mut quotient_bits = max(self.quotient_bits, other.quotient_bits);
+        while (1usize << quotient_bits) <= fingerprints.len() {
+            if quotient_bits + 1 >= fingerprint_bits {
+                return Err(QuotientFilterError::Full);
+            }
+
+            quotient_bits += 1;
+        }
+
+        let mut filter = QuotientFilter::with_hasher(
+            quotient_bits,
+            fingerprint_bits - quotient_bits,
+            self.hash_function.clone()
+        );
+
+        for fingerprint in fingerprints {
+            filter.insert_fingerprint(fingerprint).unwrap();
+        }
+
+        Ok(filter)
+    }
+
+    /// Clears the quotient filter.
+    pub fn clear(&mut self) {
+        for index in 0 .. self.capacity() {
+            self.remainders[index] = 0;
+            self.metadata[index] = 0;
+        }
+
+        self.length = 0;
+    }
+
+    /// Returns the number of fingerprints in the quotient filter.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the number of slots in the quotient filter.
+    pub fn capacity(&self) -> usize {
+        self.remainders.len()
+    }
+
+    /// Returns the fraction of the slots that are in use.
+    pub fn load_factor(&self) -> f32 {
+        self.length as f32 / self.capacity() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use test_helpers::key;
+
+    #[test]
+    fn basic_tests() {
+        let mut filter = QuotientFilter::new(4, 8);
+        let a = vec![1, 2, 3, 4];
+
+        assert_eq!(false, filter.contains(a.as_slice()));
+        assert_eq!(Ok(()), filter.insert(a.as_slice()));
+        assert_eq!(true, filter.contains(a.as_slice()));
+        assert_eq!(1, filter.length());
+
+        assert_eq!(true, filter.delete(a.as_slice()));
+        assert_eq!(false, filter.contains(a.as_slice()));
+        assert_eq!(0, filter.length());
+    }
+
+    /// With 10-bit fingerprints every possible fingerprint can be checked against a set.
+    #[test]
+    fn matches_a_set_of_fingerprints() {
+        let mut filter = QuotientFilter::new(6, 4);
+        let mut expected = HashSet::new();
+
+        for i in 0 .. 1_000 {
+            let fingerprint = filter.fingerprint(&key(i));
+
+            if i % 3 == 2 {
+                filter.delete(&key(i - 1));
+                expected.remove(&filter.fingerprint(&key(i - 1)));
+            } else if filter.insert(&key(i)).is_ok() {
+                expected.insert(fingerprint);
+            }
+
+            assert_eq!(expected.len(), filter.length());
+            for fingerprint in 0 .. 1u64 << 10 {
+                assert_eq!(expected.contains(&fingerprint), filter.contains_fingerprint(fingerprint));
+            }
+        }
+
+        let mut fingerprints = filter.fingerprints();
+        fingerprints.sort();
+        let mut sorted: Vec<u64> = expected.into_iter().collect();
+        sorted.sort();
+        assert_eq!(sorted, fingerprints);
+    }
+
+    #[test]
+    fn full() {
+        let mut filter = QuotientFilter::new(3, 8);
+        let mut i = 0;
+
+        while filter.insert(&key(i)).is_ok() {
+            i += 1;
+        }
+
+        assert_eq!(7, filter.length());
+        assert_eq!(Err(QuotientFilterError::Full), filter.insert(&key(i)));
+    }
+
+    #[test]
+    fn resize() {
+        let mut filter = QuotientFilter::new(4, 12);
+
+        for i in 0 .. 15 {
+            filter.insert(&key(i)).unwrap();
+        }
+
+        filter.resize();
+        assert_eq!(32, filter.capacity());
+        assert_eq!(15, filter.length());
+
+        for i in 15 .. 30 {
+            filter.insert(&key(i)).unwrap();
+        }
+
+        for i in 0 .. 30 {
+            assert!(filter.contains(&key(i)));
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = QuotientFilter::new(4, 12);
+        let mut b = QuotientFilter::new(5, 11);
+
+        for i in 0 .. 10 {
+            a.insert(&key(i)).unwrap();
+            b.insert(&key(i + 10)).unwrap();
+        }
+
+        let merged = a.merge(&b).unwrap();
+        assert_eq!(32, merged.capacity());
+        assert_eq!(20, merged.length());
+
+        for i in 0 .. 20 {
+            assert!(merged.contains(&key(i)));
+        }
+    }
+
+    /// Merging nearly full filters with few remainder bits moves the remainder bits to the quotient until only
+    /// one is left, and then gives up.
+    #[test]
+    fn merge_small_remainders() {
+        let mut a = QuotientFilter::new(3, 2);
+        let mut i = 0;
+        while a.insert(&key(i)).is_ok() {
+            i += 1;
+        }
+
+        // Every value of `b` has a fingerprint that `a` does not, so the merged filter needs another quotient bit.
+        let mut b = QuotientFilter::new(3, 2);
+        while a.contains(&key(i)) {
+            i += 1;
+        }
+        b.insert(&key(i)).unwrap();
+
+        let merged = a.merge(&b).unwrap();
+        assert_eq!(16, merged.capacity());
+        assert_eq!(8, merged.length());
+        assert_eq!(1, merged.remainder_bits);
+        assert_eq!(7, a.merge(&a).unwrap().length());
+
+        let mut a = QuotientFilter::new(4, 1);
+        let mut b = QuotientFilter::new(4, 1);
+        let mut i = 0;
+        while a.insert(&key(i)).is_ok() {
+            i += 1;
+        }
+        while a.contains(&key(i)) {
+            i += 1;
+        }
+        b.insert(&key(i)).unwrap();
+
+        assert_eq!(15, a.length());
+        assert_eq!(Err(QuotientFilterError::Full), a.merge(&b).map(|_| ()));
+    }
+
+    #[test]
+    fn hash_functions() {
+        use algorithms::hash::{CityHash64, XxHash64};
+
+        let mut city = QuotientFilter::with_hasher(8, 8, CityHash64);
+        let mut xxhash = QuotientFilter::with_hasher(8, 8, XxHash64);
+
+        for i in 0 .. 200 {
+            city.insert(&key(i)).unwrap();
+            xxhash.insert(&key(i)).unwrap();
+        }
+
+        let city = city.merge(&city).unwrap();
+        xxhash.resize();
+
+        for i in 0 .. 200 {
+            assert!(city.contains(&key(i)));
+            assert!(xxhash.contains(&key(i)));
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::f32;
+use std::mem;
+use algorithms::hash::{HashFunction, Murmur3};
+use data_structures::bloom_filter::BloomFilter;
+
+static DEFAULT_GROWTH_FACTOR: u32 = 2u32;
+static DEFAULT_TIGHTENING_RATIO: f32 = 0.9f32;
+
+/// An implementation of a scalable bloom filter (Almeida et al., 2007).
+///
+/// A scalable bloom filter is a chain of bloom filters. When the current filter has received as many
+/// items as it was sized for, a new filter is appended that is `growth_factor` times larger and whose
+/// error rate is tightened by `tightening_ratio`. The error rates form a geometric series, which keeps
+/// the compound false-positive rate below the configured target no matter how many items are added.
+pub struct ScalableBloomFilter<H = Murmur3> {
+    filters: Vec<BloomFilter<H>>,
+    initial_length: u32,
+    false_positive_rate: f32,
+    growth_factor: u32,
+    tightening_ratio: f32,
+    length: u32,
+    hash_function: H,
+}
+
+impl ScalableBloomFilter {
+    /// Constructs a new scalable bloom filter.
+    ///
+    /// The first stage is sized for `initial_length` items, and the compound false-positive rate is
+    /// kept below `false_positive_rate`. The stages double in size and their error rates tighten by `0.9`.
+    pub fn new(initial_length: u32, false_positive_rate: f32) -> ScalableBloomFilter {
+        ScalableBloomFilter::with_parameters(
+            initial_length,
+            false_positive_rate,
+            DEFAULT_GROWTH_FACTOR,
+            DEFAULT_TIGHTENING_RATIO
+        )
+    }
+
+    /// Constructs a new scalable bloom filter with a custom growth factor and tightening ratio.
+    ///
+    /// The initial length has to be positive, and the false-positive rate and the tightening ratio must be within
+    /// `(0, 1)`. Almeida et al. suggest a tightening ratio between `0.8` and `0.9`.
+    pub fn with_parameters(
+        initial_length: u32,
+        false_positive_rate: f32,
+        growth_factor: u32,
+        tightening_ratio: f32
+    ) -> ScalableBloomFilter {
+        ScalableBloomFilter::with_parameters_and_hasher(
+            initial_length,
+            false_positive_rate,
+            growth_factor,
+            tightening_ratio,
+            Murmur3
+        )
+    }
+}
+
+impl<H: HashFunction + Clone> ScalableBloomFilter<H> {
+    /// Constructs a new scalable bloom filter with a custom growth factor and tightening ratio, whose stages use the
+    /// given hash function.
+    pub fn with_parameters_and_hasher(
+        initial_length: u32,
+        false_positive_rate: f32,
+        growth_factor: u32,
+        tightening_ratio: f32,
+        hash_function: H
+    ) -> ScalableBloomFilter<H> {
+        if initial_length == 0 {
+            panic!("the initial length of a scalable bloom filter has to be positive");
+        }
+
+        // The ranges are written so that NaN is rejected as well.
+        if !(false_positive_rate > 0f32 && false_positive_rate < 1f32) {
+            panic!("the false-positive rate {} of a scalable bloom filter has to be within (0, 1)", false_positive_rate);
+        }
+
+        if !(tightening_ratio > 0f32 && tightening_ratio < 1f32) {
+            panic!("the tightening ratio {} has to be within (0, 1)", tightening_ratio);
+        }
+
+        if growth_factor == 0 {
+            panic!("the growth factor has to be at least 1");
+        }
+
+        let mut filter = ScalableBloomFilter {
+            filters: Vec::new(),
+            initial_length: initial_length,
+            false_positive_rate: false_positive_rate,
+            growth_factor: growth_factor,
+            tightening_ratio: tightening_ratio,
+            length: 0,
+            hash_function: hash_function,
+        };
+
+        filter.add_stage();
+        filter
+    }
+
+    /// Returns the error rate of the given stage: `P * (1 - r) * r^i`.
+    ///
+    /// The sum over all stages converges to `P`, the configured false-positive rate. The rate is computed in `f64`
+    /// and floored at the smallest normal `f32`, because it would otherwise underflow to zero after enough stages,
+    /// e.g. after 150 stages when `r = 0.5`. The stages past the floor add a negligible `1.2e-38` each to the
+    /// compound rate.
+    fn stage_false_positive_rate(&self, stage: usize) -> f32 {
+        let ratio = self.tightening_ratio as f64;
+        let rate = self.false_positive_rate as f64 * (1f64 - ratio) * ratio.powi(stage as i32);
+        rate.max(f32::MIN_POSITIVE as f64) as f32
+    }
+
+    fn add_stage(&mut self) {
+        let stage = self.filters.len();
+        let expected_length = self.initial_length.saturating_mul(self.growth_factor.saturating_pow(stage as u32));
+        let filter = BloomFilter::with_rate_and_hasher(
+            self.stage_false_positive_rate(stage),
+            expected_length,
+            self.hash_function.clone()
+        );
+
+        self.filters.push(filter);
+        self.length = 0;
+    }
+
+    /// Pushes a new value to the bloom filter.
+    ///
+    /// A new stage is added if the current one is full. Values that are already (possibly) contained
+    /// are not added again, so that they do not use up the capacity of the current stage.
+    pub fn push(&mut self, data: &[u8]) {
+        if self.contains(data) {
+            return;
+        }
+
+        if self.length >= self.filters[self.filters.len() - 1].expected_length() {
+            self.add_stage();
+        }
+
+        let last = self.filters.len() - 1;
+        self.filters[last].push(data);
+        self.length += 1;
+    }
+
+    /// Returns false if the data was definitely not added to the bloom filter, and true if it may have been.
+    pub fn contains(&self, data: &[u8]) -> bool {
+        self.filters.iter().any(|filter| filter.contains(data))
+    }
+
+    /// Clears the bloom filter and drops every stage but the first.
+    pub fn clear(&mut self) {
+        self.filters.clear();
+        self.add_stage();
+    }
+
+    /// Returns the expected compound false-positive rate: `1 - (1 - p0) * (1 - p1) * ...`.
+    pub fn expected_false_positive_rate(&self) -> f32 {
+        let rate = self.filters.iter().fold(1f32, |acc, filter| acc * (1f32 - filter.expected_false_positive_rate()));
+        1f32 - rate
+    }
+
+    /// Returns the number of bloom filter stages.
+    pub fn stage_count(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns the approximate memory use in bytes, including the bits of every stage.
+    pub fn memory_usage(&self) -> usize {
+        let bits = self.filters.iter().fold(0usize, |acc, filter| acc + filter.capacity() as usize);
+        mem::size_of::<ScalableBloomFilter<H>>() + self.filters.len() * mem::size_of::<BloomFilter<H>>() + bits.div_ceil(8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::key;
+
+    #[test]
+    fn basic_tests() {
+        let mut filter = ScalableBloomFilter::new(10, 0.01);
+        let a = vec![1, 2, 3, 4];
+        assert_eq!(false, filter.contains(a.as_slice()));
+        filter.push(a.as_slice());
+        assert_eq!(true, filter.contains(a.as_slice()));
+        assert_eq!(1, filter.stage_count());
+    }
+
+    #[test]
+    fn grows_while_keeping_false_positive_rate() {
+        let mut filter = ScalableBloomFilter::new(100, 0.01);
+
+        for i in 0 .. 10_000 {
+            filter.push(&key(i));
+        }
+
+        assert!(filter.stage_count() > 1);
+        assert!(filter.expected_false_positive_rate() < 0.01);
+
+        for i in 0 .. 10_000 {
+            assert!(filter.contains(&key(i)));
+        }
+
+        let false_positives = (10_000 .. 110_000).filter(|&i| filter.contains(&key(i))).count();
+        assert!(false_positives < 1_000, "{} false positives", false_positives);
+
+        let memory = filter.memory_usage();
+        filter.clear();
+        assert_eq!(1, filter.stage_count());
+        assert!(filter.memory_usage() < memory);
+    }
+
+    /// With a growth factor of one, the stage rates keep shrinking until they would underflow to zero.
+    #[test]
+    fn many_stages() {
+        let mut filter = ScalableBloomFilter::with_parameters(10, 0.01, 1, 0.5);
+
+        for i in 0 .. 2_000 {
+            filter.push(&key(i));
+        }
+
+        assert!(filter.stage_count() > 150, "{} stages", filter.stage_count());
+        assert!(filter.expected_false_positive_rate() < 0.01);
+        assert!((0 .. 2_000).all(|i| filter.contains(&key(i))));
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        use std::panic;
+
+        assert!(panic::catch_unwind(|| ScalableBloomFilter::new(0, 0.01)).is_err());
+
+        for &rate in [0.0, 1.0, -0.01, f32::NAN].iter() {
+            assert!(panic::catch_unwind(|| ScalableBloomFilter::new(10, rate)).is_err(), "rate {}", rate);
+        }
+
+        for &ratio in [0.0, 1.0, f32::NAN].iter() {
+            assert!(panic::catch_unwind(|| ScalableBloomFilter::with_parameters(10, 0.01, 2, ratio)).is_err());
+        }
+    }
+}
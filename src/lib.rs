@@ -8,6 +8,25 @@
 //! Insertion sort | O(n) | O(n^2) | O(n^2) | O(1)
 //! Merge sort | O(n log n) | O(n log n) | O(n log n) | O(n)
 //! Quick sort | O(n log n) | O(n log n) | O(n^2) | O(log n)
+//! Introsort | O(n) | O(n log n) | O(n log n) | O(log n)
+//! TimSort | O(n) | O(n log n) | O(n log n) | O(n)
+//! LSD radix sort[1] | O(w n) | O(w n) | O(w n) | O(n)
+//! American flag sort[1] | O(n) | O(w n) | O(w n) | O(w)
+//! Counting sort[2] | O(n + k) | O(n + k) | O(n + k) | O(n + k)
+//! Shell sort[3] | O(n log n) | Depends on gaps | Depends on gaps | O(1)
+//! Comb sort[4] | O(n log n) | O(n^2 / 2^p) | O(n^2) | O(1)
+//! Cycle sort[5] | O(n^2) | O(n^2) | O(n^2) | O(1)
+//!
+//! [1]: Radix sorts do not compare the keys, but distribute them by their bytes, where `w` is the number of bytes of the keys.
+//!
+//! [2]: Counting sort counts the occurrences of every key, where `k` is the range of the keys.
+//!
+//! [3]: Shell sort's performance depends on its gap sequence. The worst case is `O(n^2)` with Shell's gaps, `O(n^(3/2))`
+//! with Knuth's and `O(n^(4/3))` with Sedgewick's. Ciura's gaps are the fastest in practice, but their complexity is unknown.
+//!
+//! [4]: Where `p` is the number of times the gap is shrunk.
+//!
+//! [5]: Cycle sort writes every element at most once, which is the minimum of any in-place sort.
 //!
 //! # Data structures
 //!
@@ -32,6 +51,9 @@
 //! :------------: | :----------: | :--------:
 //! HeapArray | O(n) | O(n)
 //! ArrayList | O(n) | O(n)
+//! HashMap | O(1) | O(n)
+//! ChainedHashMap | O(1) | O(n)
+//! ChainedHashSet | O(1) | O(n)
 //!
 //! ### Insertion performance
 //!
@@ -43,6 +65,9 @@
 //! :------------: | :-------: | :----------: | :-------: | :--------:
 //! HeapArray[1] | N/A | N/A | N/A | N/A
 //! ArrayList[2] | O(1) | O(n) | O(n - index) | O(n)
+//! HashMap[3] | O(1) | O(1) | O(1) | O(n)
+//! ChainedHashMap[4] | O(1) | O(1) | O(1) | O(n)
+//! ChainedHashSet[4] | O(1) | O(1) | O(1) | O(n)
 //!
 //! [1]: HeapArray is fixed-size thus this function is not available.
 //!
@@ -51,6 +76,12 @@
 //!
 //! Further more, if the capacity of the list is exceeded, it will be `O(n)` as the entire list has to be reallocated.
 //!
+//! [3]: HashMap moves all entries to a table of twice the size when its load factor is exceeded, which makes that
+//! insertion `O(n)`.
+//!
+//! [4]: ChainedHashMap and ChainedHashSet rehash incrementally, moving a few buckets on every insertion and removal,
+//! so growing the table does not make a single insertion `O(n)`. The worst case is a chain of colliding keys.
+//!
 //! ### Deletion performance
 //!
 //! Deletion is the act of removing an element from the data structure. This is usually achieved with
@@ -60,6 +91,9 @@
 //! :------------: | :-------: | :----------: | :-------: | :--------:
 //! HeapArray[1] | N/A | N/A | N/A | N/A
 //! ArrayList[2] | O(1) | O(n) | O(n - index) | O(n)
+//! HashMap | O(1) | O(1) | O(1) | O(n)
+//! ChainedHashMap | O(1) | O(1) | O(1) | O(n)
+//! ChainedHashSet | O(1) | O(1) | O(1) | O(n)
 //!
 //! [1]: HeapArray is fixed-size thus this function is not available.
 //!
@@ -73,19 +107,41 @@
 //! :------------: | :----------:
 //! HeapArray | O(n)
 //! ArrayList | O(n)
+//! HashMap | O(n)
+//! ChainedHashMap | O(n)
+//! ChainedHashSet | O(n)
+//!
+//! ### Iteration stability
+//!
+//! Iteration stability defines whether the iteration order of a data structure is kept when it is modified.
+//! An iterator borrows the data structure, so a data structure cannot be modified while it is being iterated over.
+//!
+//! Data structure | Iteration order | Kept after insertion | Kept after deletion
+//! :------------: | :-------------: | :------------------: | :-----------------:
+//! HeapArray | By index | N/A | N/A
+//! ArrayList | By index | Yes[1] | Yes[1]
+//! HashMap | Arbitrary | No[2] | No[3]
+//! ChainedHashMap | Arbitrary | No[4] | No[4]
+//! ChainedHashSet | Arbitrary | No[4] | No[4]
+//!
+//! [1]: The elements after the index move, but the relative order of the other elements is kept.
+//!
+//! [2]: A Robin Hood insertion displaces the entries after the slot it takes, and growing the table reorders all
+//! entries.
+//!
+//! [3]: A backward-shift deletion moves the entries after the deleted one.
+//!
+//! [4]: The other entries keep their relative order, unless a rehash is running or the insertion starts one. During
+//! a rehash, every insertion and removal moves a few buckets to the new table, which reorders their entries.
 
 #![doc(html_root_url="https://kaisellgren.github.io/doc")]
 #![allow(unused_imports)]
 #![allow(dead_code)]
-#![feature(alloc)]
 #![feature(test)]
-#![feature(heap_api)]
-#![feature(oom)]
 
 extern crate rand;
 extern crate test;
 extern crate core;
-extern crate alloc;
 extern crate rustc_serialize;
 extern crate bit_set;
 
@@ -93,6 +149,9 @@ pub mod algorithms;
 pub mod data_structures;
 pub mod programs;
 
+#[cfg(test)]
+mod test_helpers;
+
 fn assert_sorted<T: Ord>(data: &[T]) {
     let mut index = 1;
     while index < data.len() {
@@ -105,7 +164,7 @@ fn assert_sorted<T: Ord>(data: &[T]) {
 ///
 /// This technique sorts the vector before removing the duplicates and thus is not stable.
 pub fn remove_duplicates_by_sorting<'a, A: PartialEq + Ord>(data: &'a mut Vec<A>) {
-    algorithms::quick_sort::quick_sort(data.as_mut_slice());
+    algorithms::intro_sort::intro_sort(data.as_mut_slice());
 
     let mut current_index = 0;
 
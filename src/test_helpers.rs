@@ -0,0 +1,18 @@
+//! Fixtures shared by the tests.
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+/// Returns a random number generator with a fixed seed, so that the tests are reproducible.
+pub fn rng() -> XorShiftRng {
+    XorShiftRng::from_seed([1, 2, 3, 4])
+}
+
+/// Returns `size` random numbers below `max`, which are the same on every call.
+pub fn random_data(size: usize, max: u32) -> Vec<u32> {
+    let mut rng = rng();
+    (0 .. size).map(|_| rng.gen_range(0, max)).collect()
+}
+
+/// Returns the little-endian bytes of the number, which serve as distinct keys for the hash-based structures.
+pub fn key(i: u32) -> [u8; 4] {
+    [i as u8, (i >> 8) as u8, (i >> 16) as u8, (i >> 24) as u8]
+}